@@ -0,0 +1,164 @@
+//! Deterministic pre-funded dev accounts
+//!
+//! Accounts are derived from `IsomerConfig::mnemonic` (BIP84 or BIP86 on
+//! regtest), so the same mnemonic always yields the same addresses and keys.
+
+use crate::bitcoin_rpc::BitcoinRpc;
+use crate::config::{AccountType, AccountsConfig, IsomerConfig};
+use crate::error::{IsomerError, Result};
+use crate::state::Account;
+use bitcoin::bip32::{DerivationPath, Xpriv};
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{Address, CompressedPublicKey, Network, PrivateKey};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Return the configured mnemonic, generating and persisting one if absent
+pub fn ensure_mnemonic(config: &mut IsomerConfig) -> Result<String> {
+    if let Some(ref mnemonic) = config.mnemonic {
+        return Ok(mnemonic.clone());
+    }
+
+    let mnemonic = bip39::Mnemonic::generate(12)
+        .map_err(|e| IsomerError::Config {
+            reason: format!("Failed to generate mnemonic: {}", e),
+        })?
+        .to_string();
+    config.mnemonic = Some(mnemonic.clone());
+    config
+        .save()
+        .map_err(|e| IsomerError::io("Failed to save config", e))?;
+
+    tracing::info!("Generated new dev mnemonic");
+    Ok(mnemonic)
+}
+
+/// Derive the configured number of accounts from a mnemonic
+pub fn derive_accounts(mnemonic: &str, settings: &AccountsConfig) -> Result<Vec<Account>> {
+    let mnemonic = bip39::Mnemonic::parse(mnemonic).map_err(|e| IsomerError::Config {
+        reason: format!("Invalid mnemonic: {}", e),
+    })?;
+    let seed = mnemonic.to_seed("");
+
+    let secp = Secp256k1::new();
+    let master = Xpriv::new_master(Network::Regtest, &seed).map_err(|e| IsomerError::Config {
+        reason: format!("Failed to derive master key: {}", e),
+    })?;
+
+    let purpose = match settings.account_type {
+        AccountType::Bip84 => 84,
+        AccountType::Bip86 => 86,
+    };
+
+    (0..settings.count)
+        .map(|index| {
+            // Coin type 1 is shared by all test networks, including regtest
+            let path_str = format!("m/{}'/1'/0'/0/{}", purpose, index);
+            let path = DerivationPath::from_str(&path_str).map_err(|e| IsomerError::Config {
+                reason: format!("Invalid derivation path {}: {}", path_str, e),
+            })?;
+            let child = master
+                .derive_priv(&secp, &path)
+                .map_err(|e| IsomerError::Config {
+                    reason: format!("Failed to derive {}: {}", path_str, e),
+                })?;
+
+            let private_key = PrivateKey::new(child.private_key, Network::Regtest);
+            let address = match settings.account_type {
+                AccountType::Bip84 => {
+                    let pubkey = CompressedPublicKey::from_private_key(&secp, &private_key)
+                        .map_err(|e| IsomerError::Config {
+                            reason: format!("Failed to derive public key: {}", e),
+                        })?;
+                    Address::p2wpkh(&pubkey, Network::Regtest)
+                }
+                AccountType::Bip86 => {
+                    let (internal_key, _) = child.to_keypair(&secp).x_only_public_key();
+                    Address::p2tr(&secp, internal_key, None, Network::Regtest)
+                }
+            };
+
+            Ok(Account {
+                index,
+                address: address.to_string(),
+                private_key: private_key.to_wif(),
+                derivation_path: path_str,
+                balance_sats: 0,
+            })
+        })
+        .collect()
+}
+
+/// Derive accounts for a config, or none if it has no mnemonic yet
+pub fn accounts_for_config(config: &IsomerConfig) -> Vec<Account> {
+    let Some(ref mnemonic) = config.mnemonic else {
+        return Vec::new();
+    };
+
+    match derive_accounts(mnemonic, &config.accounts) {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            tracing::error!("Failed to derive accounts: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Build `scantxoutset` descriptors covering every account
+pub fn scan_descriptors(accounts: &[Account]) -> serde_json::Value {
+    accounts
+        .iter()
+        .map(|a| serde_json::json!({ "desc": format!("addr({})", a.address) }))
+        .collect()
+}
+
+/// Sum a `scantxoutset` result into balances (in sats) keyed by address
+pub fn balances_from_scan(
+    scan_result: &serde_json::Value,
+    accounts: &[Account],
+) -> HashMap<String, u64> {
+    // Unspents are reported by scriptPubKey, so map those back to addresses
+    let by_script: HashMap<String, &str> = accounts
+        .iter()
+        .filter_map(|a| {
+            let address = Address::from_str(&a.address).ok()?.assume_checked();
+            Some((address.script_pubkey().to_hex_string(), a.address.as_str()))
+        })
+        .collect();
+
+    let mut balances = HashMap::new();
+    let unspents = scan_result
+        .get("unspents")
+        .and_then(|u| u.as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    for unspent in unspents {
+        let script = unspent.get("scriptPubKey").and_then(|s| s.as_str());
+        let amount = unspent.get("amount").and_then(|a| a.as_f64());
+        if let (Some(address), Some(amount)) = (script.and_then(|s| by_script.get(s)), amount) {
+            *balances.entry(address.to_string()).or_insert(0) += (amount * 1e8).round() as u64;
+        }
+    }
+
+    balances
+}
+
+/// Refresh `balance_sats` for each account from the live UTXO set
+pub async fn refresh_balances(config: &IsomerConfig, accounts: &mut [Account]) -> Result<()> {
+    if accounts.is_empty() {
+        return Ok(());
+    }
+
+    let result = BitcoinRpc::from_config(config)
+        .with_timeout(std::time::Duration::from_secs(10))
+        .scan_tx_out_set(scan_descriptors(accounts))
+        .await?;
+
+    let balances = balances_from_scan(&result, accounts);
+    for account in accounts.iter_mut() {
+        account.balance_sats = balances.get(&account.address).copied().unwrap_or(0);
+    }
+
+    Ok(())
+}
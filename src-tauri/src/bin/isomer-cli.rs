@@ -0,0 +1,772 @@
+//! Headless Isomer CLI
+//!
+//! Drives the same service stack as the desktop app without opening a window,
+//! for CI runners and remote machines without a display.
+
+use clap::{Parser, Subcommand};
+use isomer_lib::accounts;
+use isomer_lib::binary_manager::{BinaryManager, BinaryStatus, ALKANES_WASM_ID};
+use isomer_lib::chain;
+use isomer_lib::config::IsomerConfig;
+use isomer_lib::error::IsomerError;
+use isomer_lib::indexers::{self, IndexerStatus};
+use isomer_lib::log_files::{self, LogSearch};
+use isomer_lib::log_stream::LogLevel;
+use isomer_lib::process_manager::{ProcessManager, ServiceId};
+use isomer_lib::profiles::{self, Profile};
+use isomer_lib::reindex::{self, ReindexRequest};
+use isomer_lib::reorg::{self, ReorgRequest};
+use isomer_lib::snapshots::{self, SnapshotInfo};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(
+    name = "isomer-cli",
+    version,
+    about = "Headless control for the Isomer Alkanes development stack"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Start all services and keep them running until interrupted
+    Start,
+    /// Stop the active profile's services left running by another Isomer session
+    Stop,
+    /// Show service health and chain status
+    Status,
+    /// Stop all services and wipe chain data
+    Reset,
+    /// Print service logs, including those from earlier sessions
+    Logs {
+        /// Only show logs for this service (e.g. bitcoind, metashrew)
+        service: Option<ServiceId>,
+        /// Number of lines to show
+        #[arg(short = 'n', long, default_value_t = 100)]
+        limit: usize,
+        /// Only show lines logged at or after this Unix timestamp
+        #[arg(long)]
+        since: Option<u64>,
+        /// Only show lines logged at or before this Unix timestamp
+        #[arg(long)]
+        until: Option<u64>,
+        /// Only show lines matching this regular expression
+        #[arg(long)]
+        grep: Option<String>,
+        /// Only show lines at or above this level (trace, debug, info, warn, error)
+        #[arg(long)]
+        level: Option<LogLevel>,
+    },
+    /// Mine blocks
+    Mine {
+        /// Number of blocks to mine
+        #[arg(default_value_t = 1)]
+        count: u32,
+        /// Address receiving the coinbase rewards
+        #[arg(long)]
+        address: Option<String>,
+    },
+    /// Wait until every running indexer has caught up with a block height
+    WaitForSync {
+        /// Height to wait for (defaults to bitcoind's current height)
+        height: Option<u64>,
+        /// Seconds to wait before giving up
+        #[arg(long, default_value_t = indexers::DEFAULT_SYNC_TIMEOUT_SECS)]
+        timeout: u64,
+    },
+    /// Replace the last blocks with a competing branch and check the indexers follow
+    Reorg {
+        /// Number of blocks to disconnect from the tip
+        depth: u32,
+        /// Number of replacement blocks to mine (defaults to depth)
+        #[arg(long)]
+        blocks: Option<u32>,
+        /// Address receiving the coinbase rewards
+        #[arg(long)]
+        address: Option<String>,
+        /// Only mine this mempool transaction into the new branch (repeatable)
+        #[arg(long = "keep", value_name = "TXID")]
+        keep: Vec<String>,
+        /// Leave this mempool transaction out of the new branch (repeatable)
+        #[arg(long = "drop", value_name = "TXID")]
+        drop: Vec<String>,
+        /// Seconds to wait for the indexers to converge
+        #[arg(long, default_value_t = indexers::DEFAULT_SYNC_TIMEOUT_SECS)]
+        timeout: u64,
+    },
+    /// Install a new alkanes.wasm and rebuild the Metashrew index (stack must be stopped)
+    Reindex {
+        /// alkanes.wasm to install (keeps the current one if omitted)
+        #[arg(long)]
+        wasm: Option<PathBuf>,
+        /// Delete the whole index and rebuild it from genesis
+        #[arg(long, conflicts_with = "truncate_to")]
+        wipe: bool,
+        /// Keep the index up to this height and rebuild the blocks after it
+        #[arg(long, value_name = "HEIGHT")]
+        truncate_to: Option<u64>,
+        /// Seconds to wait for the kept blocks to be replayed
+        #[arg(long, default_value_t = reindex::DEFAULT_REINDEX_TIMEOUT_SECS)]
+        timeout: u64,
+    },
+    /// Send BTC from the dev wallet to an address
+    Faucet {
+        /// Destination address
+        address: String,
+        /// Amount in BTC
+        #[arg(default_value_t = 1.0)]
+        amount: f64,
+    },
+    /// List the pre-funded dev accounts and their balances
+    Accounts,
+    /// Download missing service binaries
+    Download {
+        /// Install from a local bundle directory or .tar.gz instead of the network
+        #[arg(long)]
+        from: Option<PathBuf>,
+    },
+    /// List, install and pin service binary versions
+    #[command(subcommand)]
+    Binary(BinaryCommand),
+    /// Manage named chain profiles
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+    /// Save and restore chain snapshots
+    #[command(subcommand)]
+    Snapshot(SnapshotCommand),
+}
+
+#[derive(Subcommand)]
+enum BinaryCommand {
+    /// List known and installed versions of a service
+    List { service: ServiceId },
+    /// Download a version next to the installed ones
+    Install { service: ServiceId, version: String },
+    /// Run this version of the service in the active profile, installing it if needed
+    Use { service: ServiceId, version: String },
+    /// Go back to the newest installed version
+    Unpin { service: ServiceId },
+    /// Show services with a newer release and its release notes
+    Updates,
+    /// Install the newest release, keeping the current one for rollback
+    /// (`alkanes-wasm` upgrades the Metashrew indexer module)
+    Upgrade { service: String },
+    /// Undo the last upgrade of a service or of `alkanes-wasm`
+    Rollback { service: String },
+    /// Run a locally built binary (clears the override without a path)
+    SetPath {
+        service: ServiceId,
+        path: Option<PathBuf>,
+    },
+    /// Load a locally built alkanes.wasm (clears the override without a path)
+    SetWasm {
+        service: ServiceId,
+        path: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommand {
+    /// Archive the active profile's chain data and config
+    Create {
+        name: String,
+        /// Write the snapshot here instead of the snapshots directory
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// List snapshots in the snapshots directory
+    List,
+    /// Replace the active profile's chain data with a snapshot (name or file path)
+    Restore { snapshot: String },
+    /// Delete a snapshot (name or file path)
+    Delete { snapshot: String },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List profiles
+    List,
+    /// Create an empty profile
+    Create { name: String },
+    /// Copy a profile's config and chain data into a new profile
+    Clone { source: String, name: String },
+    /// Make a profile active
+    Switch { name: String },
+    /// Delete a profile and its data
+    Delete { name: String },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive("isomer=info".parse().unwrap()),
+        )
+        .init();
+
+    let cli = Cli::parse();
+    let profile = Profile::active();
+    let mut config = profile.load_config();
+    if let Err(e) = accounts::ensure_mnemonic(&mut config) {
+        eprintln!("warning: {}", e);
+    }
+
+    let result = match cli.command {
+        Command::Start => start(profile, &config).await,
+        Command::Stop => {
+            ProcessManager::kill_profile_services(&profile, &config);
+            Ok(())
+        }
+        Command::Status => status(&config).await,
+        Command::Reset => ProcessManager::new(profile, &config).reset_data(),
+        Command::Logs {
+            service,
+            limit,
+            since,
+            until,
+            grep,
+            level,
+        } => logs(
+            &profile,
+            LogSearch {
+                pattern: grep,
+                services: service.map(|s| vec![s.id().to_string()]),
+                min_level: level,
+                since,
+                until,
+                limit: Some(limit),
+            },
+        ),
+        Command::Mine { count, address } => {
+            let address = address.unwrap_or_else(|| {
+                accounts::accounts_for_config(&config)
+                    .first()
+                    .map(|a| a.address.clone())
+                    .unwrap_or_else(|| chain::DEFAULT_MINING_ADDRESS.to_string())
+            });
+            chain::mine_blocks(&config, count, &address)
+                .await
+                .map(|height| println!("Mined {} block(s), height is now {}", count, height))
+        }
+        Command::WaitForSync { height, timeout } => wait_for_sync(&config, height, timeout).await,
+        Command::Reorg {
+            depth,
+            blocks,
+            address,
+            keep,
+            drop,
+            timeout,
+        } => {
+            let request = ReorgRequest {
+                depth,
+                blocks,
+                address,
+                keep_txids: (!keep.is_empty()).then_some(keep),
+                drop_txids: drop,
+                timeout_secs: Some(timeout),
+            };
+            simulate_reorg(&config, request).await
+        }
+        Command::Reindex {
+            wasm,
+            wipe,
+            truncate_to,
+            timeout,
+        } => {
+            let request = ReindexRequest {
+                wasm,
+                wipe,
+                truncate_to,
+                timeout_secs: Some(timeout),
+            };
+            reindex_metashrew(profile, &config, request).await
+        }
+        Command::Faucet { address, amount } => chain::faucet(&config, &address, amount)
+            .await
+            .map(|txid| println!("{}", txid)),
+        Command::Accounts => list_accounts(&config).await,
+        Command::Download { from } => download(&config, from).await,
+        Command::Binary(command) => manage_binaries(&profile, &config, command).await,
+        Command::Profile(command) => manage_profiles(command),
+        Command::Snapshot(command) => manage_snapshots(&profile, &config, command).await,
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            if let IsomerError::BinaryNotFound { .. } = e {
+                eprintln!("hint: run `isomer-cli download` to install the service binaries");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Start the stack in the foreground and stop it again on Ctrl+C / SIGTERM
+async fn start(profile: Profile, config: &IsomerConfig) -> Result<(), IsomerError> {
+    let missing: Vec<String> = BinaryManager::from_config(&config.binaries)
+        .check_all()
+        .into_iter()
+        .filter(|info| matches!(info.status, BinaryStatus::NotInstalled))
+        .map(|info| info.service)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Missing binaries: {}. Run `isomer-cli download` first.",
+            missing.join(", ")
+        )
+        .into());
+    }
+
+    let manager = ProcessManager::new(profile, config);
+    manager.start_all().await?;
+    eprintln!(
+        "Isomer is running (profile {}). Press Ctrl+C to stop.",
+        manager.profile().name
+    );
+
+    shutdown_signal().await;
+
+    eprintln!("Stopping services...");
+    manager.stop_all()
+}
+
+async fn status(config: &IsomerConfig) -> Result<(), IsomerError> {
+    for service in ServiceId::all() {
+        let healthy = ProcessManager::probe_health(service, config).await;
+        println!(
+            "{:<26} {:>5}  {}{}",
+            service.display_name(),
+            service.primary_port(&config.ports),
+            if healthy { "running" } else { "stopped" },
+            if config.binaries.is_custom(service) {
+                "  (custom)"
+            } else {
+                ""
+            }
+        );
+    }
+
+    let conflicts: Vec<_> = ProcessManager::find_port_conflicts(config)
+        .into_iter()
+        .filter(|c| !c.is_isomer)
+        .collect();
+    if !conflicts.is_empty() {
+        println!();
+        for conflict in conflicts {
+            println!(
+                "Port {} ({}) is in use by {} (PID {})",
+                conflict.port,
+                conflict.service.display_name(),
+                conflict.process_name,
+                conflict.pid
+            );
+        }
+    }
+
+    if ProcessManager::probe_health(ServiceId::Bitcoind, config).await {
+        let info = chain::get_chain_info(config).await;
+        println!();
+        println!("Block height: {}", info.block_height);
+        println!("Mempool size: {}", info.mempool_size);
+
+        let running = running_indexers(config).await;
+        for tip in indexers::get_tips(&running, config).await {
+            let status = IndexerStatus::new(tip, info.block_height);
+            match (status.height, status.lag, &status.error) {
+                (Some(height), Some(lag), _) => {
+                    println!("{:<10} height {} ({} behind)", status.service, height, lag)
+                }
+                (_, _, Some(e)) => println!("{:<10} {}", status.service, e),
+                _ => println!("{:<10} unknown", status.service),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn logs(profile: &Profile, query: LogSearch) -> Result<(), IsomerError> {
+    for entry in log_files::search(&profile.logs_dir(), &query)? {
+        println!(
+            "[{}] {:<5} {}",
+            entry.service,
+            format!("{:?}", entry.level).to_uppercase(),
+            entry.message
+        );
+    }
+    Ok(())
+}
+
+fn manage_profiles(command: ProfileCommand) -> Result<(), IsomerError> {
+    match command {
+        ProfileCommand::List => {
+            for profile in profiles::list() {
+                let marker = if profile.is_active() { "*" } else { " " };
+                println!(
+                    "{} {:<20} {}",
+                    marker,
+                    profile.name,
+                    profile.root().display()
+                );
+            }
+            Ok(())
+        }
+        ProfileCommand::Create { name } => Profile::create(&name).map(|_| ()),
+        ProfileCommand::Clone { source, name } => {
+            Profile::named(&source)?.clone_to(&name).map(|_| ())
+        }
+        ProfileCommand::Switch { name } => {
+            // Services belong to the profile they were started from
+            let active = Profile::active();
+            if !ProcessManager::find_profile_services(&active, &active.load_config()).is_empty() {
+                return Err(format!(
+                    "Profile {} has services running. Stop them first (`isomer-cli stop`).",
+                    active.name
+                )
+                .into());
+            }
+            Profile::named(&name)?.activate()
+        }
+        ProfileCommand::Delete { name } => Profile::named(&name)?.delete(),
+    }
+}
+
+async fn manage_snapshots(
+    profile: &Profile,
+    config: &IsomerConfig,
+    command: SnapshotCommand,
+) -> Result<(), IsomerError> {
+    let print = |info: &SnapshotInfo| {
+        let height = info
+            .manifest
+            .block_height
+            .map(|h| h.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<24} height {:>6}  {:>8.1} MB  {}",
+            info.manifest.name,
+            height,
+            info.size_bytes as f64 / 1e6,
+            info.path
+        );
+    };
+
+    match command {
+        SnapshotCommand::List => {
+            snapshots::list().iter().for_each(print);
+            Ok(())
+        }
+        SnapshotCommand::Delete { snapshot } => Ok(snapshots::delete(&snapshot)?),
+        SnapshotCommand::Create { name, output } => {
+            ensure_stopped(config).await?;
+            let info = snapshots::create(profile, config, &name, None, output.as_deref())?;
+            print(&info);
+            Ok(())
+        }
+        SnapshotCommand::Restore { snapshot } => {
+            ensure_stopped(config).await?;
+            let path = snapshots::resolve(&snapshot);
+            let (manifest, restored) = snapshots::restore(profile, config, &path)?;
+            profile.save_config(&restored)?;
+            println!("Restored snapshot {}", manifest.name);
+            Ok(())
+        }
+    }
+}
+
+async fn wait_for_sync(
+    config: &IsomerConfig,
+    height: Option<u64>,
+    timeout: u64,
+) -> Result<(), IsomerError> {
+    let height = match height {
+        Some(height) => height,
+        None => chain::get_block_count(config).await?,
+    };
+    let running = running_indexers(config).await;
+
+    indexers::wait_for_sync(config, &running, height, Duration::from_secs(timeout)).await?;
+    println!("All indexers reached height {}", height);
+    Ok(())
+}
+
+/// Indexers that respond to health checks
+async fn running_indexers(config: &IsomerConfig) -> Vec<ServiceId> {
+    let mut running = Vec::new();
+    for service in indexers::INDEXERS {
+        if ProcessManager::probe_health(service, config).await {
+            running.push(service);
+        }
+    }
+    running
+}
+
+/// alkanes.wasm is only replaced while Metashrew is down
+async fn ensure_metashrew_stopped(config: &IsomerConfig) -> Result<(), IsomerError> {
+    if ProcessManager::probe_health(ServiceId::Metashrew, config).await {
+        return Err("Metashrew is running; stop the stack first (`isomer-cli stop`)".into());
+    }
+    Ok(())
+}
+
+/// Swap alkanes.wasm and reset the index while the stack is down; Metashrew
+/// rebuilds the rest on the next `start`
+async fn reindex_metashrew(
+    profile: Profile,
+    config: &IsomerConfig,
+    request: ReindexRequest,
+) -> Result<(), IsomerError> {
+    ensure_metashrew_stopped(config).await?;
+
+    let mut manager = ProcessManager::new(profile, config);
+    let last = std::sync::Mutex::new(String::new());
+    reindex::reindex(&mut manager, &request, &|progress| {
+        let mut last = last.lock().unwrap();
+        if *last != progress.message {
+            eprintln!("{}", progress.message);
+            *last = progress.message;
+        }
+    })
+    .await
+}
+
+async fn simulate_reorg(config: &IsomerConfig, request: ReorgRequest) -> Result<(), IsomerError> {
+    let address = accounts::accounts_for_config(config)
+        .first()
+        .map(|a| a.address.clone())
+        .unwrap_or_else(|| chain::DEFAULT_MINING_ADDRESS.to_string());
+
+    let running = running_indexers(config).await;
+    let report = reorg::reorg(config, &request, &address, &running).await?;
+
+    println!(
+        "Reorged from {} ({}) to {} ({}), fork point {}",
+        report.old_tip.height,
+        report.old_tip.hash,
+        report.new_tip.height,
+        report.new_tip.hash,
+        report.fork_point.height
+    );
+    println!(
+        "{} transaction(s) mined in the new branch, {} left in the mempool",
+        report.mined_txids.len(),
+        report.dropped_txids.len()
+    );
+    for txid in &report.dropped_txids {
+        println!("  dropped {}", txid);
+    }
+
+    for tip in &report.indexers {
+        let state = if tip.is_at(report.new_tip.height, &report.new_tip.hash) {
+            "converged"
+        } else {
+            "behind"
+        };
+        match (&tip.height, &tip.error) {
+            (Some(height), _) => println!("{:<10} {:<9} height {}", tip.service, state, height),
+            (None, Some(e)) => println!("{:<10} {:<9} {}", tip.service, "unknown", e),
+            (None, None) => println!("{:<10} unknown", tip.service),
+        }
+    }
+
+    if report.converged {
+        println!("All indexers converged in {}ms", report.wait_ms);
+        Ok(())
+    } else {
+        Err(format!(
+            "Indexers did not converge within {}s",
+            request.timeout_secs.unwrap_or_default()
+        )
+        .into())
+    }
+}
+
+/// Snapshots need the databases closed, so refuse while a stack is running
+async fn ensure_stopped(config: &IsomerConfig) -> Result<(), IsomerError> {
+    if ProcessManager::probe_health(ServiceId::Bitcoind, config).await {
+        return Err("Services are running. Stop them first (`isomer-cli stop`).".into());
+    }
+    Ok(())
+}
+
+async fn list_accounts(config: &IsomerConfig) -> Result<(), IsomerError> {
+    let mut accounts = accounts::accounts_for_config(config);
+    if let Err(e) = accounts::refresh_balances(config, &mut accounts).await {
+        eprintln!("warning: could not fetch balances: {}", e);
+    }
+
+    for account in &accounts {
+        println!(
+            "{:>2}  {}  {:>14.8} BTC  {}",
+            account.index,
+            account.address,
+            account.balance_sats as f64 / 1e8,
+            account.private_key
+        );
+    }
+    Ok(())
+}
+
+async fn download(config: &IsomerConfig, from: Option<PathBuf>) -> Result<(), IsomerError> {
+    let mut manager = BinaryManager::from_config(&config.binaries);
+    let report = |service: ServiceId, progress: f32| {
+        if progress >= 1.0 {
+            eprintln!("{} installed", service.display_name());
+        }
+    };
+
+    if let Some(bundle) = from {
+        return manager.install_from_bundle(&bundle, report).await;
+    }
+
+    manager.download_alkanes_wasm().await?;
+    manager.download_all(report).await
+}
+
+async fn manage_binaries(
+    profile: &Profile,
+    config: &IsomerConfig,
+    command: BinaryCommand,
+) -> Result<(), IsomerError> {
+    let mut manager = BinaryManager::from_config(&config.binaries);
+    let report = |progress: f32| {
+        if progress >= 1.0 {
+            eprintln!("installed");
+        }
+    };
+
+    match command {
+        BinaryCommand::List { service } => {
+            if let Some(path) = config.binaries.paths.get(&service) {
+                println!("* {:<20} {}", "custom", path.display());
+            }
+            for version in manager.list_versions(service) {
+                let marker = if version.active { "*" } else { " " };
+                let mut notes = Vec::new();
+                if version.pinned {
+                    notes.push("pinned");
+                }
+                if !version.installed {
+                    notes.push("not installed");
+                }
+                println!("{} {:<20} {}", marker, version.version, notes.join(", "));
+            }
+        }
+        BinaryCommand::Install { service, version } => {
+            manager.install_version(service, &version, report).await?;
+        }
+        BinaryCommand::Use { service, version } => {
+            if !BinaryManager::installed_versions(service).contains(&version) {
+                manager.install_version(service, &version, report).await?;
+            }
+            let mut config = config.clone();
+            BinaryManager::pin_version(&mut config.binaries, service, Some(version.clone()))?;
+            profile.save_config(&config)?;
+            println!(
+                "{} now runs {} in profile {} (restart it to apply)",
+                service.display_name(),
+                version,
+                profile.name
+            );
+        }
+        BinaryCommand::Unpin { service } => {
+            let mut config = config.clone();
+            BinaryManager::pin_version(&mut config.binaries, service, None)?;
+            profile.save_config(&config)?;
+        }
+        BinaryCommand::Updates => {
+            manager.fetch_updates().await?;
+            for info in manager.check_all() {
+                if let BinaryStatus::UpdateAvailable {
+                    current,
+                    latest,
+                    changelog_urls,
+                } = info.status
+                {
+                    println!("{}: {} -> {}", info.service, current, latest);
+                    for url in changelog_urls {
+                        println!("  {}", url);
+                    }
+                }
+            }
+        }
+        BinaryCommand::Upgrade { service } if service == ALKANES_WASM_ID => {
+            ensure_metashrew_stopped(config).await?;
+            let version = manager.upgrade_alkanes_wasm().await?;
+            println!("alkanes.wasm upgraded to {}", version);
+            eprintln!(
+                "hint: run `isomer-cli reindex` to rebuild blocks indexed with the old module"
+            );
+        }
+        BinaryCommand::Upgrade { service } => {
+            let service: ServiceId = service.parse()?;
+            let version = manager.upgrade(service, report).await?;
+            println!(
+                "{} upgraded to {} (restart it to apply)",
+                service.display_name(),
+                version
+            );
+        }
+        BinaryCommand::Rollback { service } if service == ALKANES_WASM_ID => {
+            ensure_metashrew_stopped(config).await?;
+            let version = BinaryManager::rollback_alkanes_wasm().await?;
+            println!(
+                "alkanes.wasm rolled back to {}",
+                version.as_deref().unwrap_or("the previous copy")
+            );
+        }
+        BinaryCommand::Rollback { service } => {
+            let service: ServiceId = service.parse()?;
+            let version = BinaryManager::rollback(service)?;
+            println!(
+                "{} rolled back to {}",
+                service.display_name(),
+                version.as_deref().unwrap_or("the previous copy")
+            );
+        }
+        BinaryCommand::SetPath { service, path } => {
+            let mut config = config.clone();
+            manager.set_binary_override(&mut config.binaries, service, path)?;
+            profile.save_config(&config)?;
+        }
+        BinaryCommand::SetWasm { service, path } => {
+            let mut config = config.clone();
+            BinaryManager::set_wasm_override(&mut config.binaries, service, path)?;
+            profile.save_config(&config)?;
+        }
+    }
+    Ok(())
+}
+
+/// Wait for Ctrl+C, or SIGTERM on Unix (what CI runners send on cancellation)
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -2,13 +2,16 @@
 //!
 //! Handles downloading, verifying, and updating service binaries
 
-use crate::config::get_bin_dir;
+use crate::config::{get_bin_dir, BinariesConfig};
+use crate::downloads;
+use crate::error::{IsomerError, Result};
 use crate::process_manager::ServiceId;
+use crate::profiles;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Status of a binary
@@ -16,9 +19,18 @@ use std::process::Command;
 #[serde(rename_all = "lowercase")]
 pub enum BinaryStatus {
     NotInstalled,
-    Downloading { progress: f32 },
-    Installed { version: String },
-    UpdateAvailable { current: String, latest: String },
+    Downloading {
+        progress: f32,
+    },
+    Installed {
+        version: String,
+    },
+    UpdateAvailable {
+        current: String,
+        latest: String,
+        /// Release notes of every newer version, newest first
+        changelog_urls: Vec<String>,
+    },
 }
 
 impl BinaryStatus {
@@ -34,10 +46,29 @@ impl BinaryStatus {
 /// Information about a binary
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BinaryInfo {
+    /// Service id, e.g. `bitcoind`
+    pub id: String,
     pub service: String,
     pub status: BinaryStatus,
     pub path: String,
     pub size_bytes: Option<u64>,
+    /// The last upgrade can be undone
+    pub can_roll_back: bool,
+    /// Runs a locally built binary or alkanes.wasm from the config
+    pub custom: bool,
+}
+
+/// A version of a service binary, installed or known to the app
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinaryVersion {
+    pub version: String,
+    pub installed: bool,
+    /// Runs when the service starts
+    pub active: bool,
+    /// Selected in the profile's config rather than by default
+    pub pinned: bool,
+    /// Can be downloaded
+    pub available: bool,
 }
 
 /// Binary release information
@@ -51,19 +82,108 @@ pub struct BinaryRelease {
     pub archive_path: Option<String>,
     /// Whether this is a tar.gz archive that needs extraction
     pub is_archive: bool,
+    /// Release notes
+    #[serde(default)]
+    pub changelog_url: Option<String>,
+}
+
+/// Releases announced after the app was built, from the remote release manifest
+#[derive(Debug, Clone, Deserialize)]
+struct RemoteRelease {
+    version: String,
+    changelog_url: Option<String>,
+    /// Download per platform, keyed like `darwin-arm64`
+    platforms: HashMap<String, RemoteAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RemoteAsset {
+    url: String,
+    #[serde(default)]
+    sha256: String,
+    #[serde(default)]
+    size_bytes: u64,
+    archive_path: Option<String>,
+}
+
+/// Remote release manifest as fetched, before it is merged into a manager's
+/// known releases. Cheap to clone so callers can cache it.
+#[derive(Debug, Clone)]
+pub struct ReleaseManifest {
+    releases: HashMap<String, Vec<RemoteRelease>>,
+    /// Whether it came with a valid signature
+    signed: bool,
+}
+
+/// Upgrade that can be undone, kept next to a service's versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RollbackRecord {
+    /// Active version before the upgrade; `None` for an unversioned copy in `bin/`
+    previous: Option<String>,
+    upgraded: String,
+}
+
+/// Installed alkanes.wasm, recorded next to it in `alkanes.wasm.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WasmManifest {
+    /// Release version, or `local` for a module installed from a file
+    pub version: String,
+    pub sha256: String,
 }
 
 /// Manages binary downloads and updates
 pub struct BinaryManager {
-    releases: HashMap<ServiceId, BinaryRelease>,
+    /// Known releases per service, newest first
+    releases: HashMap<ServiceId, Vec<BinaryRelease>>,
+    /// Known alkanes.wasm releases, newest first
+    wasm_releases: Vec<BinaryRelease>,
+    /// Pinned versions and checksum policy
+    config: BinariesConfig,
     /// Cached checksums fetched from the release
     checksums_cache: Option<HashMap<String, String>>,
+    /// Whether `checksums_cache` came with a valid signature
+    checksums_signed: bool,
+    /// Whether the remote release manifest has been merged into `releases`
+    updates_checked: bool,
 }
 
 /// URL for the checksums.json file in the release
 const CHECKSUMS_URL: &str =
     "https://github.com/jonatns/isomer/releases/download/binaries-v0.1.3/checksums.json";
 
+/// Release manifest listing the newest binaries for every platform
+const RELEASE_MANIFEST_URL: &str =
+    "https://raw.githubusercontent.com/jonatns/isomer/main/release-manifest.json";
+
+/// URL of the alkanes.wasm indexer module loaded by metashrew. Checksums are
+/// keyed by file name, so it must come from the same release as `CHECKSUMS_URL`.
+const ALKANES_WASM_URL: &str =
+    "https://github.com/jonatns/isomer/releases/download/binaries-v0.1.3/alkanes.wasm";
+
+/// Release of alkanes.wasm built into the app
+const ALKANES_WASM_VERSION: &str = "0.1.3";
+
+/// Id of alkanes.wasm in `BinaryInfo`, upgrades and the release manifest
+pub const ALKANES_WASM_ID: &str = "alkanes-wasm";
+
+/// Version recorded for an alkanes.wasm installed from a local file
+pub const LOCAL_WASM_VERSION: &str = "local";
+
+/// Version and sha256 of the installed alkanes.wasm
+const WASM_MANIFEST_FILE: &str = "alkanes.wasm.json";
+
+/// Services that load alkanes.wasm
+const WASM_SERVICES: [ServiceId; 1] = [ServiceId::Metashrew];
+
+/// Checksum manifest of a binaries release (or offline bundle)
+const CHECKSUMS_FILE: &str = "checksums.json";
+
+/// minisign signature of the checksum manifest
+const CHECKSUMS_SIGNATURE_FILE: &str = "checksums.json.minisig";
+
+/// minisign public key that signs the checksum manifest of binaries releases
+const MANIFEST_PUBLIC_KEY: &str = "RWRH90+1Yb+bvhk6v5Es3vTuoiJMu7n7Kz70pH9NQX0xCh00vmFdQ5QG";
+
 impl Default for BinaryManager {
     fn default() -> Self {
         Self::new()
@@ -72,14 +192,23 @@ impl Default for BinaryManager {
 
 impl BinaryManager {
     pub fn new() -> Self {
+        Self::from_config(&BinariesConfig::default())
+    }
+
+    /// Manager using a profile's pinned versions and checksum policy
+    pub fn from_config(config: &BinariesConfig) -> Self {
         Self {
             releases: Self::get_releases_for_platform(),
+            wasm_releases: Self::alkanes_wasm_releases(),
+            config: config.clone(),
             checksums_cache: None,
+            checksums_signed: false,
+            updates_checked: false,
         }
     }
 
     /// Fetch checksums from the release
-    pub async fn fetch_checksums(&mut self) -> Result<(), String> {
+    pub async fn fetch_checksums(&mut self) -> Result<()> {
         if self.checksums_cache.is_some() {
             return Ok(());
         }
@@ -91,23 +220,84 @@ impl BinaryManager {
             .get(CHECKSUMS_URL)
             .send()
             .await
-            .map_err(|e| format!("Failed to fetch checksums: {}", e))?;
+            .map_err(|e| IsomerError::download(CHECKSUMS_URL, e))?;
 
         if !response.status().is_success() {
-            tracing::warn!("Failed to fetch checksums, will skip verification");
+            // Strict mode would refuse every file without a built-in checksum anyway
+            if self.config.strict_checksums {
+                return Err(IsomerError::download(
+                    CHECKSUMS_URL,
+                    format!("status {}", response.status()),
+                ));
+            }
+            tracing::warn!("Failed to fetch checksums ({})", response.status());
             return Ok(());
         }
 
-        let checksums: HashMap<String, String> = response
-            .json()
+        let content = response
+            .text()
             .await
-            .map_err(|e| format!("Failed to parse checksums: {}", e))?;
+            .map_err(|e| IsomerError::download(CHECKSUMS_URL, e))?;
+
+        let signature_url = format!("{}.minisig", CHECKSUMS_URL);
+        let signature = match client.get(&signature_url).send().await {
+            Ok(response) if response.status().is_success() => Some(
+                response
+                    .text()
+                    .await
+                    .map_err(|e| IsomerError::download(&signature_url, e))?,
+            ),
+            _ => None,
+        };
+
+        self.load_checksums(&content, signature.as_deref())
+    }
 
-        tracing::info!("Loaded {} checksums", checksums.len());
+    /// Use a checksum manifest, checking its signature if there is one.
+    ///
+    /// A signature that does not verify is always an error; an unsigned
+    /// manifest is only trusted outside strict mode.
+    fn load_checksums(&mut self, content: &str, signature: Option<&str>) -> Result<()> {
+        let signed = match signature {
+            Some(signature) => {
+                Self::verify_signature(content.as_bytes(), signature)?;
+                true
+            }
+            None => {
+                tracing::warn!("{} is not signed", CHECKSUMS_FILE);
+                false
+            }
+        };
+
+        let checksums: HashMap<String, String> =
+            serde_json::from_str(content).map_err(|e| IsomerError::Config {
+                reason: format!("Invalid {}: {}", CHECKSUMS_FILE, e),
+            })?;
+
+        tracing::info!(
+            "Loaded {} checksums ({})",
+            checksums.len(),
+            if signed { "signed" } else { "unsigned" }
+        );
         self.checksums_cache = Some(checksums);
+        self.checksums_signed = signed;
         Ok(())
     }
 
+    /// Check a minisign signature against the embedded release key
+    fn verify_signature(content: &[u8], signature: &str) -> Result<()> {
+        let unverified = |reason: String| IsomerError::Unverified {
+            file: CHECKSUMS_FILE.to_string(),
+            reason,
+        };
+        let key = minisign_verify::PublicKey::from_base64(MANIFEST_PUBLIC_KEY)
+            .map_err(|e| unverified(format!("invalid release key: {}", e)))?;
+        let signature = minisign_verify::Signature::decode(signature)
+            .map_err(|e| unverified(format!("malformed signature: {}", e)))?;
+        key.verify(content, &signature, false)
+            .map_err(|_| unverified("signature does not match the release key".to_string()))
+    }
+
     /// Get checksum for a filename from the cache
     fn get_checksum_for_file(&self, filename: &str) -> Option<String> {
         self.checksums_cache
@@ -138,49 +328,51 @@ impl BinaryManager {
         (os, arch)
     }
 
-    /// Get the latest release info for all binaries based on platform
-    fn get_releases_for_platform() -> HashMap<ServiceId, BinaryRelease> {
+    /// Get the known releases of all binaries for the platform, newest first
+    fn get_releases_for_platform() -> HashMap<ServiceId, Vec<BinaryRelease>> {
         let mut releases = HashMap::new();
         let (os, arch) = Self::get_platform();
 
         // Bitcoin Core
-        let (btc_url, btc_sha) = if os == "darwin" && arch == "arm64" {
-            (
-                "https://bitcoincore.org/bin/bitcoin-core-29.2/bitcoin-29.2-arm64-apple-darwin.tar.gz",
-                "bd07450f76d149d094842feab58e6240673120c8a317a1c51d45ba30c34e85ef",
-            )
+        let btc_triple = if os == "darwin" && arch == "arm64" {
+            "arm64-apple-darwin"
         } else if os == "darwin" && arch == "x86_64" {
-            (
-                "https://bitcoincore.org/bin/bitcoin-core-29.2/bitcoin-29.2-x86_64-apple-darwin.tar.gz",
-                "69ca05fbe838123091cf4d6d2675352f36cf55f49e2e6fb3b52fcf32b5e8dd9f",
-            )
-        } else if os == "linux" && arch == "x86_64" {
-            (
-                "https://bitcoincore.org/bin/bitcoin-core-29.2/bitcoin-29.2-x86_64-linux-gnu.tar.gz",
-                "1fd58d0ae94b8a9e21bbaeab7d53395a44976e82bd5492b0a894826c135f9009",
-            )
+            "x86_64-apple-darwin"
         } else if os == "linux" && arch == "arm64" {
-            (
-                "https://bitcoincore.org/bin/bitcoin-core-29.2/bitcoin-29.2-aarch64-linux-gnu.tar.gz",
-                "f88f72a3c5bf526581aae573be8c1f62133eaecfe3d34646c9ffca7b79dfdc7a",
-            )
+            "aarch64-linux-gnu"
         } else {
-            (
-                "https://bitcoincore.org/bin/bitcoin-core-29.2/bitcoin-29.2-x86_64-linux-gnu.tar.gz",
-                "1fd58d0ae94b8a9e21bbaeab7d53395a44976e82bd5492b0a894826c135f9009",
-            )
+            "x86_64-linux-gnu"
+        };
+        let btc_29_sha = match btc_triple {
+            "arm64-apple-darwin" => {
+                "bd07450f76d149d094842feab58e6240673120c8a317a1c51d45ba30c34e85ef"
+            }
+            "x86_64-apple-darwin" => {
+                "69ca05fbe838123091cf4d6d2675352f36cf55f49e2e6fb3b52fcf32b5e8dd9f"
+            }
+            "aarch64-linux-gnu" => {
+                "f88f72a3c5bf526581aae573be8c1f62133eaecfe3d34646c9ffca7b79dfdc7a"
+            }
+            _ => "1fd58d0ae94b8a9e21bbaeab7d53395a44976e82bd5492b0a894826c135f9009",
+        };
+        let bitcoin_core = |version: &str, sha256: &str| BinaryRelease {
+            version: version.to_string(),
+            url: format!(
+                "https://bitcoincore.org/bin/bitcoin-core-{0}/bitcoin-{0}-{1}.tar.gz",
+                version, btc_triple
+            ),
+            sha256: sha256.to_string(),
+            size_bytes: 45_000_000,
+            archive_path: Some(format!("bitcoin-{}/bin/bitcoind", version)),
+            is_archive: true,
+            changelog_url: Some(format!("https://bitcoincore.org/en/releases/{}/", version)),
         };
 
+        // 28.1 has no sha256 built in; in strict mode it needs the signed
+        // checksums.json of the binaries release, which lists the upstream archives
         releases.insert(
             ServiceId::Bitcoind,
-            BinaryRelease {
-                version: "29.2".to_string(),
-                url: btc_url.to_string(),
-                sha256: btc_sha.to_string(),
-                size_bytes: 45_000_000,
-                archive_path: Some("bitcoin-29.2/bin/bitcoind".to_string()),
-                is_archive: true,
-            },
+            vec![bitcoin_core("29.2", btc_29_sha), bitcoin_core("28.1", "")],
         );
 
         // Ord - official releases from ordinals/ord
@@ -208,14 +400,17 @@ impl BinaryManager {
 
         releases.insert(
             ServiceId::Ord,
-            BinaryRelease {
+            vec![BinaryRelease {
                 version: "0.22.1".to_string(),
                 url: ord_url.to_string(),
                 sha256: ord_sha.to_string(),
                 size_bytes: 15_000_000,
                 archive_path: Some("ord".to_string()),
                 is_archive: true,
-            },
+                changelog_url: Some(
+                    "https://github.com/ordinals/ord/releases/tag/0.22.1".to_string(),
+                ),
+            }],
         );
 
         // For metashrew binaries (rockshrew-mono, memshrew-p2p, flextrs),
@@ -223,13 +418,15 @@ impl BinaryManager {
         // would point to your release infrastructure.
         let isomer_release_base =
             "https://github.com/jonatns/isomer/releases/download/binaries-v0.1.3";
+        let isomer_changelog =
+            Some("https://github.com/jonatns/isomer/releases/tag/binaries-v0.1.3".to_string());
 
         // Determine SHA based on platform (placeholder, will be updated on next release)
         let rockshrew_sha = "";
 
         releases.insert(
             ServiceId::Metashrew,
-            BinaryRelease {
+            vec![BinaryRelease {
                 version: "9.0.2-alpha.1".to_string(),
                 // Format: rockshrew-mono-darwin-arm64
                 url: format!("{}/rockshrew-mono-{}-{}", isomer_release_base, os, arch),
@@ -237,7 +434,8 @@ impl BinaryManager {
                 size_bytes: 25_000_000,
                 archive_path: None,
                 is_archive: false,
-            },
+                changelog_url: isomer_changelog.clone(),
+            }],
         );
 
         let flextrs_sha = if os == "darwin" && arch == "arm64" {
@@ -248,20 +446,21 @@ impl BinaryManager {
 
         releases.insert(
             ServiceId::Esplora,
-            BinaryRelease {
+            vec![BinaryRelease {
                 version: "0.4.1".to_string(),
                 url: format!("{}/flextrs-{}-{}", isomer_release_base, os, arch),
                 sha256: flextrs_sha.to_string(),
                 size_bytes: 15_000_000,
                 archive_path: None,
                 is_archive: false,
-            },
+                changelog_url: isomer_changelog.clone(),
+            }],
         );
 
         // JSON-RPC is a Node.js app - bundle it differently
         releases.insert(
             ServiceId::JsonRpc,
-            BinaryRelease {
+            vec![BinaryRelease {
                 version: "0.1.0".to_string(),
                 url: format!("{}/alkanes-jsonrpc-bundle.tar.gz", isomer_release_base),
                 sha256: "bedc8928c7c48eb45ab51f9094b06a732ee7542e091cf4e75fd902e8aea84a55"
@@ -269,7 +468,8 @@ impl BinaryManager {
                 size_bytes: 10_000_000,
                 archive_path: None,
                 is_archive: true,
-            },
+                changelog_url: isomer_changelog.clone(),
+            }],
         );
 
         // Espo
@@ -277,58 +477,229 @@ impl BinaryManager {
 
         releases.insert(
             ServiceId::Espo,
-            BinaryRelease {
+            vec![BinaryRelease {
                 version: "0.1.0".to_string(),
                 url: format!("{}/espo-{}-{}", isomer_release_base, os, arch),
                 sha256: espo_sha.to_string(),
                 size_bytes: 30_000_000,
                 archive_path: None,
                 is_archive: false,
-            },
+                changelog_url: isomer_changelog.clone(),
+            }],
         );
 
         releases
     }
 
-    /// Get the path where a binary should be installed
-    fn get_binary_path(service: ServiceId) -> PathBuf {
-        get_bin_dir().join(service.binary_name())
+    /// alkanes.wasm releases built into the app
+    fn alkanes_wasm_releases() -> Vec<BinaryRelease> {
+        vec![BinaryRelease {
+            version: ALKANES_WASM_VERSION.to_string(),
+            url: ALKANES_WASM_URL.to_string(),
+            // No sha256 built in yet; checked against checksums.json when it
+            // lists alkanes.wasm
+            sha256: String::new(),
+            size_bytes: 0,
+            archive_path: None,
+            is_archive: false,
+            changelog_url: Some(
+                "https://github.com/jonatns/isomer/releases/tag/binaries-v0.1.3".to_string(),
+            ),
+        }]
+    }
+
+    /// Where a version of a service is installed
+    pub fn version_dir(service: ServiceId, version: &str) -> PathBuf {
+        get_bin_dir()
+            .join("versions")
+            .join(service.id())
+            .join(version)
+    }
+
+    /// Installed versions of a service, oldest first
+    pub fn installed_versions(service: ServiceId) -> Vec<String> {
+        let dir = get_bin_dir().join("versions").join(service.id());
+        let mut versions: Vec<String> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.path().join(service.binary_name()).exists())
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        versions.sort_by(|a, b| compare_versions(a, b));
+        versions
+    }
+
+    /// Version a service runs: the pinned one, else the newest installed one.
+    /// `None` means the unversioned copy in `bin/` from before versions were
+    /// kept side by side.
+    pub fn active_version(service: ServiceId, config: &BinariesConfig) -> Option<String> {
+        if let Some(pinned) = config.versions.get(&service) {
+            return Some(pinned.clone());
+        }
+        if let Some(newest) = Self::installed_versions(service).pop() {
+            return Some(newest);
+        }
+        if get_bin_dir().join(service.binary_name()).exists() {
+            return None;
+        }
+        Self::get_releases_for_platform()
+            .remove(&service)
+            .and_then(|releases| releases.into_iter().next())
+            .map(|release| release.version)
+    }
+
+    /// Binary (or bundle directory, for JSON-RPC) a service runs: the
+    /// configured override, else the active version
+    pub fn binary_path(service: ServiceId, config: &BinariesConfig) -> PathBuf {
+        if let Some(path) = config.paths.get(&service) {
+            return path.clone();
+        }
+        match Self::active_version(service, config) {
+            Some(version) => Self::version_dir(service, &version).join(service.binary_name()),
+            None => get_bin_dir().join(service.binary_name()),
+        }
+    }
+
+    /// alkanes.wasm a service loads: the configured override, else the downloaded one
+    pub fn alkanes_wasm_path(service: ServiceId, config: &BinariesConfig) -> PathBuf {
+        config
+            .wasm_paths
+            .get(&service)
+            .cloned()
+            .unwrap_or_else(|| get_bin_dir().join("alkanes.wasm"))
+    }
+
+    fn get_binary_path(&self, service: ServiceId) -> PathBuf {
+        Self::binary_path(service, &self.config)
     }
 
     /// Check if a binary is installed
-    pub fn is_installed(service: ServiceId) -> bool {
-        Self::get_binary_path(service).exists()
+    pub fn is_installed(&self, service: ServiceId) -> bool {
+        self.get_binary_path(service).exists()
+    }
+
+    /// Known release of a service version
+    fn release(&self, service: ServiceId, version: &str) -> Option<&BinaryRelease> {
+        self.releases
+            .get(&service)
+            .and_then(|releases| releases.iter().find(|r| r.version == version))
+    }
+
+    /// Release to install for a service: the pinned version, else the newest
+    fn target_release(&self, service: ServiceId) -> Result<&BinaryRelease> {
+        let releases = self
+            .releases
+            .get(&service)
+            .ok_or_else(|| format!("No release info for {}", service.display_name()))?;
+        match self.config.versions.get(&service) {
+            Some(pinned) => self.release(service, pinned).ok_or_else(|| {
+                IsomerError::invalid(format!(
+                    "{} {} is not a known release",
+                    service.display_name(),
+                    pinned
+                ))
+            }),
+            None => releases
+                .first()
+                .ok_or_else(|| format!("No release info for {}", service.display_name()).into()),
+        }
+    }
+
+    /// Pin the version a service runs, or go back to the default with `None`.
+    ///
+    /// Only installed versions can be pinned; the change applies on the next start.
+    pub fn pin_version(
+        config: &mut BinariesConfig,
+        service: ServiceId,
+        version: Option<String>,
+    ) -> Result<()> {
+        match version {
+            Some(version) => {
+                if !Self::installed_versions(service).contains(&version) {
+                    return Err(IsomerError::invalid(format!(
+                        "{} {} is not installed",
+                        service.display_name(),
+                        version
+                    )));
+                }
+                tracing::info!("Pinned {} to {}", service.display_name(), version);
+                config.versions.insert(service, version);
+            }
+            None => {
+                config.versions.remove(&service);
+            }
+        }
+        Ok(())
+    }
+
+    /// Known and installed versions of a service, newest first
+    pub fn list_versions(&self, service: ServiceId) -> Vec<BinaryVersion> {
+        let installed = Self::installed_versions(service);
+        // A locally built binary runs instead of any installed version
+        let active = if self.config.paths.contains_key(&service) {
+            None
+        } else {
+            Self::active_version(service, &self.config)
+        };
+        let pinned = self.config.versions.get(&service);
+
+        let mut versions: Vec<String> = self
+            .releases
+            .get(&service)
+            .map(|releases| releases.iter().map(|r| r.version.clone()).collect())
+            .unwrap_or_default();
+        for version in &installed {
+            if !versions.contains(version) {
+                versions.push(version.clone());
+            }
+        }
+        versions.sort_by(|a, b| compare_versions(b, a));
+
+        versions
+            .into_iter()
+            .map(|version| BinaryVersion {
+                installed: installed.contains(&version),
+                active: active.as_ref() == Some(&version),
+                pinned: pinned == Some(&version),
+                available: self.release(service, &version).is_some(),
+                version,
+            })
+            .collect()
     }
 
     /// Get status of all binaries
     pub fn check_all(&self) -> Vec<BinaryInfo> {
-        ServiceId::all()
+        let mut binaries: Vec<BinaryInfo> = ServiceId::all()
             .into_iter()
             .map(|service| self.check_binary(service))
-            .collect()
+            .collect();
+        binaries.push(self.check_alkanes_wasm());
+        binaries
     }
 
     /// Check status of a single binary
     pub fn check_binary(&self, service: ServiceId) -> BinaryInfo {
-        let path = Self::get_binary_path(service);
+        let path = self.get_binary_path(service);
         let exists = path.exists();
 
-        // let latest_version = self
-        //     .releases
-        //     .get(&service)
-        //     .map(|r| r.version.clone())
-        //     .unwrap_or_else(|| "unknown".to_string());
-
-        let status = if exists {
-            let current_version = self
-                .get_binary_version(service)
-                .unwrap_or("unknown".to_string());
-
-            // User requested to disable automatic update checks.
-            // We always return Installed if the binary exists.
+        let status = if exists && self.config.paths.contains_key(&service) {
             BinaryStatus::Installed {
-                version: current_version,
+                version: self
+                    .get_binary_version(service)
+                    .unwrap_or("custom".to_string()),
             }
+        } else if exists {
+            // Versioned installs know their version; ask older copies
+            let current_version = Self::active_version(service, &self.config)
+                .or_else(|| self.get_binary_version(service))
+                .unwrap_or("unknown".to_string());
+
+            // Only reported once updates were checked, which is opt-in
+            let newer = self.newer_releases(service, &current_version);
+            installed_status(current_version, newer)
         } else {
             BinaryStatus::NotInstalled
         };
@@ -340,16 +711,372 @@ impl BinaryManager {
         };
 
         BinaryInfo {
+            id: service.id().to_string(),
             service: service.display_name().to_string(),
             status,
             path: path.display().to_string(),
             size_bytes,
+            can_roll_back: Self::rollback_record_path(service).exists(),
+            custom: self.config.is_custom(service),
+        }
+    }
+
+    /// Check the binary and alkanes.wasm overrides that changed since `previous`
+    pub fn validate_overrides(&self, previous: &BinariesConfig) -> Result<()> {
+        for (service, path) in &self.config.paths {
+            if previous.paths.get(service) != Some(path) {
+                self.validate_binary_override(*service, path)?;
+            }
+        }
+        for (service, path) in &self.config.wasm_paths {
+            if previous.wasm_paths.get(service) != Some(path) {
+                Self::validate_wasm_override(*service, path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a locally built binary exists, is executable and answers
+    /// `--version`. Returns the version it reports, if any.
+    pub fn validate_binary_override(
+        &self,
+        service: ServiceId,
+        path: &Path,
+    ) -> Result<Option<String>> {
+        let invalid = |reason: &str| IsomerError::Config {
+            reason: format!(
+                "Custom {} binary {} {}",
+                service.display_name(),
+                path.display(),
+                reason
+            ),
+        };
+        if !path.exists() {
+            return Err(invalid("does not exist"));
+        }
+
+        // JSON-RPC runs from a bundle directory under Node.js
+        if service == ServiceId::JsonRpc {
+            if !path.join("bin/jsonrpc.js").is_file() {
+                return Err(invalid("is not a JSON-RPC bundle (missing bin/jsonrpc.js)"));
+            }
+            return Ok(None);
+        }
+
+        if !path.is_file() {
+            return Err(invalid("is not a file"));
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(path)
+                .map_err(|e| IsomerError::io(format!("Failed to read {}", path.display()), e))?
+                .permissions()
+                .mode();
+            if mode & 0o111 == 0 {
+                return Err(invalid("is not executable"));
+            }
+        }
+        match self.run_version_cmd(&path.to_path_buf(), "--version") {
+            Some(version) => Ok(Some(version)),
+            None => Err(invalid("did not answer --version")),
+        }
+    }
+
+    /// Check that a locally built alkanes.wasm exists and is a WebAssembly module
+    pub fn validate_wasm_override(service: ServiceId, path: &Path) -> Result<()> {
+        if !WASM_SERVICES.contains(&service) {
+            return Err(IsomerError::Config {
+                reason: format!("{} does not load alkanes.wasm", service.display_name()),
+            });
+        }
+        if !Self::is_wasm_module(path) {
+            return Err(IsomerError::Config {
+                reason: format!(
+                    "Custom alkanes.wasm {} does not exist or is not a WebAssembly module",
+                    path.display()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether a file starts with the WebAssembly magic number
+    pub fn is_wasm_module(path: &Path) -> bool {
+        let mut magic = [0u8; 4];
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .is_ok()
+            && magic == *b"\0asm"
+    }
+
+    /// Point a service at a locally built binary, or back at the installed
+    /// one with `None`. Applies on the next start.
+    pub fn set_binary_override(
+        &self,
+        config: &mut BinariesConfig,
+        service: ServiceId,
+        path: Option<PathBuf>,
+    ) -> Result<()> {
+        match path {
+            Some(path) => {
+                let version = self.validate_binary_override(service, &path)?;
+                tracing::info!(
+                    "Using custom {} {} from {}",
+                    service.display_name(),
+                    version.as_deref().unwrap_or("build"),
+                    path.display()
+                );
+                config.paths.insert(service, path);
+            }
+            None => {
+                config.paths.remove(&service);
+            }
+        }
+        Ok(())
+    }
+
+    /// Point a service at a locally built alkanes.wasm, or back at the
+    /// downloaded one with `None`. Applies on the next start.
+    pub fn set_wasm_override(
+        config: &mut BinariesConfig,
+        service: ServiceId,
+        path: Option<PathBuf>,
+    ) -> Result<()> {
+        match path {
+            Some(path) => {
+                Self::validate_wasm_override(service, &path)?;
+                tracing::info!(
+                    "Using custom alkanes.wasm for {} from {}",
+                    service.display_name(),
+                    path.display()
+                );
+                config.wasm_paths.insert(service, path);
+            }
+            None => {
+                config.wasm_paths.remove(&service);
+            }
+        }
+        Ok(())
+    }
+
+    /// Releases newer than the running version, newest first, if updates were
+    /// checked and the service is not pinned
+    fn newer_releases(&self, service: ServiceId, current: &str) -> Option<Vec<&BinaryRelease>> {
+        if self.config.versions.contains_key(&service) {
+            return None;
+        }
+        self.newer_than(self.releases.get(&service)?, current)
+    }
+
+    /// Releases newer than `current`, newest first, if updates were checked
+    fn newer_than<'a>(
+        &self,
+        releases: &'a [BinaryRelease],
+        current: &str,
+    ) -> Option<Vec<&'a BinaryRelease>> {
+        if !self.updates_checked || current == "unknown" || current == LOCAL_WASM_VERSION {
+            return None;
+        }
+        let newer: Vec<&BinaryRelease> = releases
+            .iter()
+            .filter(|r| compare_versions(&r.version, current) == Ordering::Greater)
+            .collect();
+        (!newer.is_empty()).then_some(newer)
+    }
+
+    /// Fetch the remote release manifest and add its releases for this platform
+    pub async fn fetch_updates(&mut self) -> Result<()> {
+        if self.updates_checked {
+            return Ok(());
+        }
+        let manifest = Self::fetch_release_manifest().await?;
+        self.apply_updates(&manifest);
+        Ok(())
+    }
+
+    /// Add the releases of an already fetched release manifest
+    pub fn apply_updates(&mut self, manifest: &ReleaseManifest) {
+        if self.updates_checked {
+            return;
+        }
+        let trust_checksums = manifest.signed || !self.config.strict_checksums;
+        self.merge_releases(manifest.releases.clone(), trust_checksums);
+        self.updates_checked = true;
+    }
+
+    /// Download the remote release manifest, checking its signature if it has one
+    pub async fn fetch_release_manifest() -> Result<ReleaseManifest> {
+        tracing::info!("Checking for binary updates at {}", RELEASE_MANIFEST_URL);
+        let client = reqwest::Client::new();
+        let response = client
+            .get(RELEASE_MANIFEST_URL)
+            .send()
+            .await
+            .map_err(|e| IsomerError::download(RELEASE_MANIFEST_URL, e))?;
+        if !response.status().is_success() {
+            return Err(IsomerError::download(
+                RELEASE_MANIFEST_URL,
+                format!("status {}", response.status()),
+            ));
+        }
+        let content = response
+            .text()
+            .await
+            .map_err(|e| IsomerError::download(RELEASE_MANIFEST_URL, e))?;
+
+        // Checksums from the manifest count as built in only if it is signed
+        let signature_url = format!("{}.minisig", RELEASE_MANIFEST_URL);
+        let signed = match client.get(&signature_url).send().await {
+            Ok(response) if response.status().is_success() => {
+                let signature = response
+                    .text()
+                    .await
+                    .map_err(|e| IsomerError::download(&signature_url, e))?;
+                Self::verify_signature(content.as_bytes(), &signature)?;
+                true
+            }
+            _ => false,
+        };
+
+        let releases = serde_json::from_str(&content)
+            .map_err(|e| IsomerError::download(RELEASE_MANIFEST_URL, e))?;
+        Ok(ReleaseManifest { releases, signed })
+    }
+
+    /// Add releases from the manifest, keyed by service id or `alkanes-wasm`.
+    /// Platform-independent files such as alkanes.wasm are listed under `any`.
+    fn merge_releases(
+        &mut self,
+        manifest: HashMap<String, Vec<RemoteRelease>>,
+        trust_checksums: bool,
+    ) {
+        let (os, arch) = Self::get_platform();
+        let platform = format!("{}-{}", os, arch);
+
+        for (id, remote) in manifest {
+            let releases = if id == ALKANES_WASM_ID {
+                &mut self.wasm_releases
+            } else {
+                match id.parse::<ServiceId>() {
+                    Ok(service) => self.releases.entry(service).or_default(),
+                    Err(_) => {
+                        tracing::debug!("Skipping unknown binary {} in release manifest", id);
+                        continue;
+                    }
+                }
+            };
+            for release in remote {
+                if let Some(known) = releases.iter_mut().find(|r| r.version == release.version) {
+                    if known.changelog_url.is_none() {
+                        known.changelog_url = release.changelog_url;
+                    }
+                    continue;
+                }
+                let Some(asset) = release
+                    .platforms
+                    .get(&platform)
+                    .or_else(|| release.platforms.get("any"))
+                else {
+                    continue;
+                };
+                releases.push(BinaryRelease {
+                    version: release.version.clone(),
+                    url: asset.url.clone(),
+                    sha256: if trust_checksums {
+                        asset.sha256.clone()
+                    } else {
+                        String::new()
+                    },
+                    size_bytes: asset.size_bytes,
+                    archive_path: asset.archive_path.clone(),
+                    is_archive: asset.url.ends_with(".tar.gz"),
+                    changelog_url: release.changelog_url.clone(),
+                });
+            }
+            releases.sort_by(|a, b| compare_versions(&b.version, &a.version));
+        }
+    }
+
+    /// Install the newest release of a service, keeping the current version for
+    /// [`BinaryManager::rollback`]. Returns the new version.
+    pub async fn upgrade(
+        &mut self,
+        service: ServiceId,
+        progress_callback: impl Fn(f32) + Send + 'static,
+    ) -> Result<String> {
+        if self.config.paths.contains_key(&service) {
+            return Err(IsomerError::invalid(format!(
+                "{} runs a custom binary; clear its path to upgrade",
+                service.display_name()
+            )));
+        }
+        if let Some(pinned) = self.config.versions.get(&service) {
+            return Err(IsomerError::invalid(format!(
+                "{} is pinned to {}; unpin it to upgrade",
+                service.display_name(),
+                pinned
+            )));
+        }
+        self.fetch_updates().await?;
+        self.fetch_checksums().await?;
+
+        let release = self.target_release(service)?.clone();
+        self.download_release(service, &release, progress_callback)
+            .await?;
+        Ok(release.version)
+    }
+
+    /// Undo the last upgrade of a service by removing the version it installed.
+    /// Returns the version that is active again (`None` for an unversioned copy).
+    ///
+    /// Versions are shared by all profiles, so this is refused while any
+    /// profile pins the version that would be removed.
+    pub fn rollback(service: ServiceId) -> Result<Option<String>> {
+        let record_path = Self::rollback_record_path(service);
+        let record: RollbackRecord = std::fs::read_to_string(&record_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .ok_or_else(|| {
+                IsomerError::invalid(format!(
+                    "{} has no upgrade to roll back",
+                    service.display_name()
+                ))
+            })?;
+
+        if let Some(profile) = profiles::list().into_iter().find(|profile| {
+            profile.load_config().binaries.versions.get(&service) == Some(&record.upgraded)
+        }) {
+            return Err(IsomerError::invalid(format!(
+                "{} {} is pinned in profile {}; unpin it to roll back",
+                service.display_name(),
+                record.upgraded,
+                profile.name
+            )));
         }
+
+        std::fs::remove_dir_all(Self::version_dir(service, &record.upgraded))
+            .map_err(|e| IsomerError::io("Failed to remove upgraded version", e))?;
+        let _ = std::fs::remove_file(&record_path);
+        tracing::info!(
+            "Rolled back {} {} to {}",
+            service.display_name(),
+            record.upgraded,
+            record.previous.as_deref().unwrap_or("the previous copy")
+        );
+        Ok(record.previous)
+    }
+
+    fn rollback_record_path(service: ServiceId) -> PathBuf {
+        get_bin_dir()
+            .join("versions")
+            .join(service.id())
+            .join("rollback.json")
     }
 
     /// Try to get version from installed binary
     fn get_binary_version(&self, service: ServiceId) -> Option<String> {
-        let path = Self::get_binary_path(service);
+        let path = self.get_binary_path(service);
         if !path.exists() {
             return None;
         }
@@ -406,10 +1133,10 @@ impl BinaryManager {
 
     /// Extract a tar.gz archive to get a specific binary
     fn extract_binary_from_tar_gz(
-        archive_data: &[u8],
+        archive_data: impl Read,
         archive_path: &str,
         dest_path: &PathBuf,
-    ) -> Result<(), String> {
+    ) -> Result<()> {
         use flate2::read::GzDecoder;
         use tar::Archive;
 
@@ -418,182 +1145,210 @@ impl BinaryManager {
 
         for entry in archive
             .entries()
-            .map_err(|e| format!("Failed to read archive: {}", e))?
+            .map_err(|e| IsomerError::io("Failed to read archive", e))?
         {
-            let mut entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+            let mut entry = entry.map_err(|e| IsomerError::io("Failed to read entry", e))?;
             let path = entry
                 .path()
-                .map_err(|e| format!("Failed to get path: {}", e))?;
+                .map_err(|e| IsomerError::io("Failed to get path", e))?;
 
             if path.ends_with(archive_path) || path.to_string_lossy() == archive_path {
                 // Ensure parent directory exists
                 if let Some(parent) = dest_path.parent() {
                     std::fs::create_dir_all(parent)
-                        .map_err(|e| format!("Failed to create directory: {}", e))?;
+                        .map_err(|e| IsomerError::io("Failed to create directory", e))?;
                 }
 
                 // Extract the file
-                let mut content = Vec::new();
-                entry
-                    .read_to_end(&mut content)
-                    .map_err(|e| format!("Failed to read binary: {}", e))?;
+                let mut file = std::fs::File::create(dest_path)
+                    .map_err(|e| IsomerError::io("Failed to write binary", e))?;
+                std::io::copy(&mut entry, &mut file)
+                    .map_err(|e| IsomerError::io("Failed to write binary", e))?;
 
-                std::fs::write(dest_path, &content)
-                    .map_err(|e| format!("Failed to write binary: {}", e))?;
-
-                // Make executable on Unix
-                #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
-                    let mut perms = std::fs::metadata(dest_path)
-                        .map_err(|e| format!("Failed to get permissions: {}", e))?
-                        .permissions();
-                    perms.set_mode(0o755);
-                    std::fs::set_permissions(dest_path, perms)
-                        .map_err(|e| format!("Failed to set permissions: {}", e))?;
-                }
+                Self::make_executable(dest_path)?;
 
                 tracing::info!("Extracted binary to {}", dest_path.display());
                 return Ok(());
             }
         }
 
-        Err(format!("Binary '{}' not found in archive", archive_path))
+        Err(format!("Binary '{}' not found in archive", archive_path).into())
     }
 
-    /// Download a binary
+    /// Make a file executable on Unix
+    fn make_executable(path: &PathBuf) -> Result<()> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(path)
+                .map_err(|e| IsomerError::io("Failed to get permissions", e))?
+                .permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(path, perms)
+                .map_err(|e| IsomerError::io("Failed to set permissions", e))?;
+        }
+        #[cfg(not(unix))]
+        let _ = path;
+        Ok(())
+    }
+
+    /// Download the pinned or newest version of a binary, or take it from the
+    /// download cache, and install it
     pub async fn download(
         &self,
         service: ServiceId,
         progress_callback: impl Fn(f32) + Send + 'static,
-    ) -> Result<(), String> {
-        let release = self
-            .releases
-            .get(&service)
-            .ok_or_else(|| format!("No release info for {}", service.display_name()))?;
+    ) -> Result<()> {
+        let release = self.target_release(service)?;
+        self.download_release(service, release, progress_callback)
+            .await
+    }
 
-        let dest_path = Self::get_binary_path(service);
+    /// Download and install a specific version next to the installed ones
+    pub async fn install_version(
+        &mut self,
+        service: ServiceId,
+        version: &str,
+        progress_callback: impl Fn(f32) + Send + 'static,
+    ) -> Result<()> {
+        let release = self.release(service, version).cloned().ok_or_else(|| {
+            IsomerError::invalid(format!(
+                "{} {} is not a known release",
+                service.display_name(),
+                version
+            ))
+        })?;
+        self.fetch_checksums().await?;
+        self.download_release(service, &release, progress_callback)
+            .await
+    }
 
-        // Ensure bin directory exists
-        if let Some(parent) = dest_path.parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create bin directory: {}", e))?;
-        }
+    async fn download_release(
+        &self,
+        service: ServiceId,
+        release: &BinaryRelease,
+        progress_callback: impl Fn(f32) + Send + 'static,
+    ) -> Result<()> {
+        let expected_checksum = self.expected_checksum(release)?;
 
         tracing::info!(
-            "Downloading {} from {}",
+            "Downloading {} {} from {}",
             service.display_name(),
+            release.version,
             release.url
         );
 
         progress_callback(0.0);
 
-        // Download the file with streaming progress
-        let client = reqwest::Client::new();
-        let response = client
-            .get(&release.url)
-            .send()
-            .await
-            .map_err(|e| format!("Download failed: {}", e))?;
+        // Download is 90% of the work, installing is the rest
+        let cached = downloads::fetch(
+            &release.url,
+            expected_checksum.as_deref(),
+            |downloaded, total| {
+                let total = total.unwrap_or(release.size_bytes).max(1);
+                progress_callback((downloaded as f32 / total as f32).min(1.0) * 0.9);
+            },
+        )
+        .await?;
 
-        if !response.status().is_success() {
-            return Err(format!(
-                "Download failed with status: {}",
-                response.status()
-            ));
+        progress_callback(0.9);
+        let previous = self
+            .is_installed(service)
+            .then(|| Self::active_version(service, &self.config));
+        Self::install(service, release, &cached)?;
+
+        if let Some(previous) = previous {
+            if is_upgrade(previous.as_deref(), &release.version) {
+                let record = RollbackRecord {
+                    previous,
+                    upgraded: release.version.clone(),
+                };
+                if let Err(e) = serde_json::to_string(&record)
+                    .map_err(|e| e.to_string())
+                    .and_then(|content| {
+                        std::fs::write(Self::rollback_record_path(service), content)
+                            .map_err(|e| e.to_string())
+                    })
+                {
+                    tracing::warn!("Failed to record rollback for {}: {}", service.id(), e);
+                }
+            }
         }
 
-        let total_size = response.content_length().unwrap_or(release.size_bytes);
-        let mut downloaded: u64 = 0;
-        let mut bytes_vec = Vec::with_capacity(total_size as usize);
-
-        // Stream the response and track progress
-        use futures_util::StreamExt;
-        let mut stream = response.bytes_stream();
-
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| format!("Failed to read chunk: {}", e))?;
-            bytes_vec.extend_from_slice(&chunk);
-            downloaded += chunk.len() as u64;
+        progress_callback(1.0);
+        tracing::info!("{} downloaded successfully", service.display_name());
+        Ok(())
+    }
 
-            // Report progress (download is 90% of total work, checksum is last 10%)
-            let progress = (downloaded as f32 / total_size as f32) * 0.9;
-            progress_callback(progress);
+    /// Checksum to verify a release file against: the release manifest if it
+    /// can be trusted, else the one built into the app. In strict mode a file
+    /// without either is refused.
+    fn expected_checksum(&self, release: &BinaryRelease) -> Result<Option<String>> {
+        let filename = downloads::file_name(&release.url);
+        let listed = self.get_checksum_for_file(filename);
+        if let Some(checksum) = listed.clone() {
+            if self.checksums_signed || !self.config.strict_checksums {
+                return Ok(Some(checksum));
+            }
+        }
+        if !release.sha256.is_empty() {
+            return Ok(Some(release.sha256.clone()));
         }
 
-        let bytes = bytes::Bytes::from(bytes_vec);
-
-        progress_callback(0.9);
-
-        // Get checksum - prefer dynamic from checksums.json, fallback to hardcoded
-        let filename = release.url.split('/').last().unwrap_or("");
-        let expected_checksum = self.get_checksum_for_file(filename).or_else(|| {
-            if !release.sha256.is_empty() {
-                Some(release.sha256.clone())
+        if self.config.strict_checksums {
+            let reason = if listed.is_some() {
+                format!("{} is not signed", CHECKSUMS_FILE)
+            } else if self.checksums_cache.is_none() {
+                format!("{} could not be loaded", CHECKSUMS_FILE)
             } else {
-                None
-            }
-        });
+                format!("{} has no entry for it", CHECKSUMS_FILE)
+            };
+            return Err(IsomerError::Unverified {
+                file: filename.to_string(),
+                reason: format!(
+                    "{} (set binaries.strict_checksums to false to install it anyway)",
+                    reason
+                ),
+            });
+        }
 
-        if let Some(expected) = expected_checksum {
-            tracing::info!("Verifying checksum for {}...", service.display_name());
-            let mut hasher = Sha256::new();
-            hasher.update(&bytes);
-            let result = hasher.finalize();
-            let digest = hex::encode(result);
+        tracing::warn!(
+            "No checksum available for {}, skipping verification",
+            filename
+        );
+        Ok(None)
+    }
 
-            if digest != expected {
-                tracing::error!(
-                    "Checksum mismatch for {}: expected {}, got {}",
-                    service.display_name(),
-                    expected,
-                    digest
-                );
-                return Err(format!(
-                    "Checksum verification failed for {}",
-                    service.display_name()
-                ));
-            }
-            tracing::info!("Checksum verified for {}", service.display_name());
-        } else {
-            tracing::warn!(
-                "No checksum available for {}, skipping verification",
-                service.display_name()
-            );
-        }
+    /// Install a verified release file into its version directory
+    fn install(service: ServiceId, release: &BinaryRelease, cached: &Path) -> Result<()> {
+        let version_dir = Self::version_dir(service, &release.version);
+        let dest_path = version_dir.join(service.binary_name());
+        std::fs::create_dir_all(&version_dir)
+            .map_err(|e| IsomerError::io("Failed to create bin directory", e))?;
 
         if release.is_archive {
+            let file = std::fs::File::open(cached)
+                .map_err(|e| IsomerError::io("Failed to open archive", e))?;
+            let file = std::io::BufReader::new(file);
             if let Some(ref archive_path) = release.archive_path {
                 // Extract specific binary from archive
-                Self::extract_binary_from_tar_gz(&bytes, archive_path, &dest_path)?;
+                Self::extract_binary_from_tar_gz(file, archive_path, &dest_path)?;
             } else {
-                // Extract entire archive to bin directory
+                // Extract entire archive to the version directory
                 use flate2::read::GzDecoder;
                 use tar::Archive;
 
-                let gz = GzDecoder::new(bytes.as_ref());
+                let gz = GzDecoder::new(file);
                 let mut archive = Archive::new(gz);
-                let bin_dir = get_bin_dir();
                 archive
-                    .unpack(&bin_dir)
-                    .map_err(|e| format!("Failed to extract archive: {}", e))?;
+                    .unpack(&version_dir)
+                    .map_err(|e| IsomerError::io("Failed to extract archive", e))?;
             }
         } else {
             // Direct binary download
-            std::fs::write(&dest_path, &bytes)
-                .map_err(|e| format!("Failed to write binary: {}", e))?;
-
-            // Make executable on Unix
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                let mut perms = std::fs::metadata(&dest_path)
-                    .map_err(|e| format!("Failed to get permissions: {}", e))?
-                    .permissions();
-                perms.set_mode(0o755);
-                std::fs::set_permissions(&dest_path, perms)
-                    .map_err(|e| format!("Failed to set permissions: {}", e))?;
-            }
+            std::fs::copy(cached, &dest_path)
+                .map_err(|e| IsomerError::io("Failed to write binary", e))?;
+            Self::make_executable(&dest_path)?;
         }
 
         // Ad-hoc sign on macOS to prevent SIGKILL
@@ -607,7 +1362,7 @@ impl BinaryManager {
                 .arg("-f") // Force
                 .arg(&dest_path)
                 .status()
-                .map_err(|e| format!("Failed to run codesign: {}", e))?;
+                .map_err(|e| IsomerError::io("Failed to run codesign", e))?;
 
             if !status.success() {
                 tracing::warn!("codesign failed for {}", dest_path.display());
@@ -615,75 +1370,643 @@ impl BinaryManager {
             }
         }
 
-        progress_callback(1.0);
-        tracing::info!("{} downloaded successfully", service.display_name());
         Ok(())
     }
 
-    /// Download all missing or outdated binaries
+    /// Services whose binary is not installed, leaving out locally built ones.
+    /// Outdated ones are only replaced through [`BinaryManager::upgrade`].
+    fn missing(&self) -> Vec<ServiceId> {
+        ServiceId::all()
+            .into_iter()
+            .filter(|service| !self.config.paths.contains_key(service))
+            .filter(|service| {
+                matches!(
+                    self.check_binary(*service).status,
+                    BinaryStatus::NotInstalled
+                )
+            })
+            .collect()
+    }
+
+    /// Download all missing binaries, a few at a time
     pub async fn download_all(
         &mut self,
         progress_callback: impl Fn(ServiceId, f32) + Send + Clone + 'static,
-    ) -> Result<(), String> {
+    ) -> Result<()> {
+        use futures_util::stream::{self, StreamExt};
+
         // Fetch checksums from release before downloading
         self.fetch_checksums().await?;
 
-        for service in ServiceId::all() {
-            let status = self.check_binary(service).status;
-            let should_download = match status {
-                BinaryStatus::NotInstalled => true,
-                BinaryStatus::UpdateAvailable { .. } => true,
-                _ => false,
-            };
-
-            if should_download {
+        let this = &*self;
+        let results: Vec<Result<()>> = stream::iter(self.missing())
+            .map(|service| {
                 let cb = progress_callback.clone();
-                self.download(service, move |p| cb(service, p)).await?;
+                async move { this.download(service, move |p| cb(service, p)).await }
+            })
+            .buffer_unordered(downloads::MAX_PARALLEL_DOWNLOADS)
+            .collect()
+            .await;
+
+        // Every download runs to completion; report the first failure
+        results.into_iter().collect()
+    }
+
+    fn alkanes_wasm_manifest_path() -> PathBuf {
+        get_bin_dir().join(WASM_MANIFEST_FILE)
+    }
+
+    /// Version and sha256 of the installed alkanes.wasm, if it was recorded
+    pub fn alkanes_wasm_manifest() -> Option<WasmManifest> {
+        std::fs::read_to_string(Self::alkanes_wasm_manifest_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    /// Status of alkanes.wasm, listed alongside the service binaries
+    pub fn check_alkanes_wasm(&self) -> BinaryInfo {
+        let path = Self::alkanes_wasm_path(ServiceId::Metashrew, &self.config);
+        let custom = self.config.wasm_paths.contains_key(&ServiceId::Metashrew);
+        let exists = path.exists();
+
+        let status = if !exists {
+            BinaryStatus::NotInstalled
+        } else if custom {
+            BinaryStatus::Installed {
+                version: "custom".to_string(),
             }
+        } else {
+            let current = Self::alkanes_wasm_manifest()
+                .map(|manifest| manifest.version)
+                .unwrap_or("unknown".to_string());
+            let newer = self.newer_than(&self.wasm_releases, &current);
+            installed_status(current, newer)
+        };
+
+        BinaryInfo {
+            id: ALKANES_WASM_ID.to_string(),
+            service: "alkanes.wasm".to_string(),
+            status,
+            path: path.display().to_string(),
+            size_bytes: if exists {
+                std::fs::metadata(&path).ok().map(|m| m.len())
+            } else {
+                None
+            },
+            can_roll_back: Self::alkanes_wasm_rollback_path().exists(),
+            custom,
+        }
+    }
+
+    /// Check the installed alkanes.wasm against its recorded sha256 before
+    /// Metashrew loads it. Copies from before the manifest existed are refused
+    /// until they are downloaded again.
+    pub fn verify_alkanes_wasm() -> Result<()> {
+        let Some(manifest) = Self::alkanes_wasm_manifest() else {
+            return Err(IsomerError::Unverified {
+                file: "alkanes.wasm".to_string(),
+                reason: format!(
+                    "it has no recorded checksum in {}; download it again",
+                    WASM_MANIFEST_FILE
+                ),
+            });
+        };
+        let actual = downloads::sha256_file_blocking(&get_bin_dir().join("alkanes.wasm"))?;
+        if actual != manifest.sha256 {
+            return Err(IsomerError::ChecksumMismatch {
+                file: "alkanes.wasm".to_string(),
+                expected: manifest.sha256,
+                actual,
+            });
         }
         Ok(())
     }
 
-    /// Download the alkanes.wasm file needed for metashrew
-    pub async fn download_alkanes_wasm() -> Result<(), String> {
-        let wasm_path = get_bin_dir().join("alkanes.wasm");
+    /// Replace the installed alkanes.wasm and record its version and sha256
+    pub async fn install_alkanes_wasm(source: &Path, version: &str) -> Result<()> {
+        if !Self::is_wasm_module(source) {
+            return Err(IsomerError::invalid(format!(
+                "{} does not exist or is not a WebAssembly module",
+                source.display()
+            )));
+        }
+        let sha256 = downloads::sha256_file(source).await?;
+
+        let bin_dir = get_bin_dir();
+        std::fs::create_dir_all(&bin_dir)
+            .map_err(|e| IsomerError::io("Failed to create bin directory", e))?;
+        // Metashrew may be reading the old module, so never leave a partial file
+        let staged = bin_dir.join("alkanes.wasm.new");
+        std::fs::copy(source, &staged)
+            .map_err(|e| IsomerError::io(format!("Failed to read {}", source.display()), e))?;
+        std::fs::rename(&staged, bin_dir.join("alkanes.wasm"))
+            .map_err(|e| IsomerError::io("Failed to write alkanes.wasm", e))?;
+
+        let manifest = WasmManifest {
+            version: version.to_string(),
+            sha256,
+        };
+        let content = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| format!("Failed to serialize {}: {}", WASM_MANIFEST_FILE, e))?;
+        std::fs::write(Self::alkanes_wasm_manifest_path(), content)
+            .map_err(|e| IsomerError::io(format!("Failed to write {}", WASM_MANIFEST_FILE), e))?;
 
-        if wasm_path.exists() {
-            tracing::info!("alkanes.wasm already exists");
+        tracing::info!("Installed alkanes.wasm {}", version);
+        Ok(())
+    }
+
+    /// Download alkanes.wasm for Metashrew, unless the newest known release
+    /// (or a local module) is already installed and intact
+    pub async fn download_alkanes_wasm(&mut self) -> Result<()> {
+        if self.config.wasm_paths.contains_key(&ServiceId::Metashrew) {
+            tracing::info!("Metashrew uses a custom alkanes.wasm");
             return Ok(());
         }
+        let release = self
+            .wasm_releases
+            .first()
+            .cloned()
+            .ok_or("No alkanes.wasm release known")?;
+
+        if let Some(manifest) = Self::alkanes_wasm_manifest() {
+            let up_to_date = manifest.version == LOCAL_WASM_VERSION
+                || compare_versions(&manifest.version, &release.version) != Ordering::Less;
+            if up_to_date && Self::verify_alkanes_wasm().is_ok() {
+                tracing::info!("alkanes.wasm {} is installed", manifest.version);
+                return Ok(());
+            }
+        }
+
+        self.fetch_checksums().await?;
+        self.download_alkanes_wasm_release(&release).await
+    }
 
-        // Ensure bin directory exists
-        if let Some(parent) = wasm_path.parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create bin directory: {}", e))?;
+    /// Install the newest alkanes.wasm release, keeping the current module for
+    /// [`BinaryManager::rollback_alkanes_wasm`]. Returns the new version.
+    ///
+    /// Metashrew must be stopped. Blocks it has already indexed keep the old
+    /// module's results; reindex to rebuild them.
+    pub async fn upgrade_alkanes_wasm(&mut self) -> Result<String> {
+        if self.config.wasm_paths.contains_key(&ServiceId::Metashrew) {
+            return Err(IsomerError::invalid(
+                "Metashrew uses a custom alkanes.wasm; clear its path to upgrade",
+            ));
         }
+        self.fetch_updates().await?;
+        self.fetch_checksums().await?;
 
-        let wasm_url =
-            "https://github.com/jonatns/isomer/releases/download/binaries-v0.1.0/alkanes.wasm";
+        let release = self
+            .wasm_releases
+            .first()
+            .cloned()
+            .ok_or("No alkanes.wasm release known")?;
+
+        let current = get_bin_dir().join("alkanes.wasm");
+        let active = Self::alkanes_wasm_manifest().map(|manifest| manifest.version);
+        // A module without a recorded checksum could not be verified after a rollback
+        let keep =
+            current.exists() && active.is_some() && is_upgrade(active.as_deref(), &release.version);
+        let previous = Self::previous_alkanes_wasm_path();
+        if keep {
+            std::fs::copy(&current, &previous)
+                .map_err(|e| IsomerError::io("Failed to keep the previous alkanes.wasm", e))?;
+        }
+        if let Err(e) = self.download_alkanes_wasm_release(&release).await {
+            if keep {
+                let _ = std::fs::remove_file(&previous);
+            }
+            return Err(e);
+        }
 
-        tracing::info!("Downloading alkanes.wasm from {}", wasm_url);
+        if keep {
+            let record = RollbackRecord {
+                previous: active,
+                upgraded: release.version.clone(),
+            };
+            if let Err(e) = serde_json::to_string(&record)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    std::fs::write(Self::alkanes_wasm_rollback_path(), content)
+                        .map_err(|e| e.to_string())
+                })
+            {
+                tracing::warn!("Failed to record rollback for alkanes.wasm: {}", e);
+            }
+        }
+        Ok(release.version)
+    }
 
-        let response = reqwest::get(wasm_url)
-            .await
-            .map_err(|e| format!("Download failed: {}", e))?;
+    /// Undo the last alkanes.wasm upgrade by putting the previous module back.
+    /// Returns the version that is active again (`None` if it was not recorded).
+    ///
+    /// Metashrew must be stopped.
+    pub async fn rollback_alkanes_wasm() -> Result<Option<String>> {
+        let record_path = Self::alkanes_wasm_rollback_path();
+        let previous = Self::previous_alkanes_wasm_path();
+        let record: RollbackRecord = std::fs::read_to_string(&record_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .filter(|_| previous.exists())
+            .ok_or_else(|| IsomerError::invalid("alkanes.wasm has no upgrade to roll back"))?;
+
+        match &record.previous {
+            Some(version) => Self::install_alkanes_wasm(&previous, version).await?,
+            None => {
+                std::fs::rename(&previous, get_bin_dir().join("alkanes.wasm"))
+                    .map_err(|e| IsomerError::io("Failed to restore alkanes.wasm", e))?;
+                let _ = std::fs::remove_file(Self::alkanes_wasm_manifest_path());
+            }
+        }
+        let _ = std::fs::remove_file(&previous);
+        let _ = std::fs::remove_file(&record_path);
+        tracing::info!(
+            "Rolled back alkanes.wasm {} to {}",
+            record.upgraded,
+            record.previous.as_deref().unwrap_or("the previous copy")
+        );
+        Ok(record.previous)
+    }
 
-        if !response.status().is_success() {
-            return Err(format!(
-                "Download failed with status: {}",
-                response.status()
-            ));
+    /// alkanes.wasm from before the last upgrade
+    fn previous_alkanes_wasm_path() -> PathBuf {
+        get_bin_dir().join("alkanes.wasm.previous")
+    }
+
+    fn alkanes_wasm_rollback_path() -> PathBuf {
+        get_bin_dir().join("alkanes.wasm.rollback.json")
+    }
+
+    async fn download_alkanes_wasm_release(&self, release: &BinaryRelease) -> Result<()> {
+        let expected = self.expected_checksum(release)?;
+
+        tracing::info!(
+            "Downloading alkanes.wasm {} from {}",
+            release.version,
+            release.url
+        );
+        let cached = downloads::fetch(&release.url, expected.as_deref(), |_, _| {}).await?;
+        Self::install_alkanes_wasm(&cached, &release.version).await
+    }
+
+    /// Install missing binaries and alkanes.wasm from an offline bundle
+    ///
+    /// `source` is a directory or a `.tar.gz` with the release files under their
+    /// download names, `alkanes.wasm`, a `checksums.json` and, for strict mode, its
+    /// `checksums.json.minisig`. Files are verified like downloads and added to the
+    /// download cache.
+    pub async fn install_from_bundle(
+        &mut self,
+        source: &Path,
+        progress_callback: impl Fn(ServiceId, f32),
+    ) -> Result<()> {
+        if source.is_dir() {
+            return self.install_from_dir(source, progress_callback).await;
         }
 
-        let bytes = response
-            .bytes()
-            .await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
+        let unpacked = downloads::cache_dir().join("bundle");
+        if unpacked.exists() {
+            std::fs::remove_dir_all(&unpacked)
+                .map_err(|e| IsomerError::io("Failed to clear bundle directory", e))?;
+        }
+        std::fs::create_dir_all(&unpacked)
+            .map_err(|e| IsomerError::io("Failed to create bundle directory", e))?;
+
+        tracing::info!("Unpacking bundle {}", source.display());
+        let file = std::fs::File::open(source)
+            .map_err(|e| IsomerError::io(format!("Failed to open {}", source.display()), e))?;
+        let mut archive =
+            tar::Archive::new(flate2::read::GzDecoder::new(std::io::BufReader::new(file)));
+        archive
+            .unpack(&unpacked)
+            .map_err(|e| IsomerError::io("Failed to extract bundle", e))?;
+
+        // Bundles may wrap everything in a single top-level directory
+        let root = if unpacked.join(CHECKSUMS_FILE).exists() {
+            unpacked.clone()
+        } else {
+            let mut entries: Vec<PathBuf> = std::fs::read_dir(&unpacked)
+                .map_err(|e| IsomerError::io("Failed to read bundle", e))?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .collect();
+            if entries.len() == 1 && entries[0].is_dir() {
+                entries.remove(0)
+            } else {
+                unpacked.clone()
+            }
+        };
 
-        std::fs::write(&wasm_path, &bytes)
-            .map_err(|e| format!("Failed to write alkanes.wasm: {}", e))?;
+        let result = self.install_from_dir(&root, progress_callback).await;
+        let _ = std::fs::remove_dir_all(&unpacked);
+        result
+    }
 
-        tracing::info!("alkanes.wasm downloaded successfully");
+    async fn install_from_dir(
+        &mut self,
+        dir: &Path,
+        progress_callback: impl Fn(ServiceId, f32),
+    ) -> Result<()> {
+        let manifest = dir.join(CHECKSUMS_FILE);
+        let content = std::fs::read_to_string(&manifest).map_err(|e| {
+            IsomerError::io(format!("Bundle has no readable {}", CHECKSUMS_FILE), e)
+        })?;
+        let signature = std::fs::read_to_string(dir.join(CHECKSUMS_SIGNATURE_FILE)).ok();
+        self.load_checksums(&content, signature.as_deref())?;
+
+        // Bundles carry the alkanes.wasm release built into the app
+        if Self::alkanes_wasm_manifest().is_none() || Self::verify_alkanes_wasm().is_err() {
+            let source = Self::bundle_file(dir, "alkanes.wasm")?;
+            let release = Self::alkanes_wasm_releases().remove(0);
+            let expected = self.expected_checksum(&release)?;
+            let cached = downloads::import(&source, expected.as_deref()).await?;
+            Self::install_alkanes_wasm(&cached, ALKANES_WASM_VERSION).await?;
+            tracing::info!("alkanes.wasm installed from bundle");
+        }
+
+        for service in self.missing() {
+            let release = self.target_release(service)?;
+            let source = Self::bundle_file(dir, downloads::file_name(&release.url))?;
+
+            progress_callback(service, 0.0);
+            let expected = self.expected_checksum(release)?;
+            let cached = downloads::import(&source, expected.as_deref()).await?;
+            progress_callback(service, 0.9);
+            Self::install(service, release, &cached)?;
+            progress_callback(service, 1.0);
+            tracing::info!("{} installed from bundle", service.display_name());
+        }
         Ok(())
     }
+
+    fn bundle_file(dir: &Path, name: &str) -> Result<PathBuf> {
+        let path = dir.join(name);
+        if path.is_file() {
+            Ok(path)
+        } else {
+            Err(format!("Bundle {} does not contain {}", dir.display(), name).into())
+        }
+    }
+}
+
+/// Installed status of a binary, with any newer releases as an available update
+fn installed_status(current: String, newer: Option<Vec<&BinaryRelease>>) -> BinaryStatus {
+    match newer {
+        Some(newer) => BinaryStatus::UpdateAvailable {
+            current,
+            latest: newer[0].version.clone(),
+            changelog_urls: newer
+                .iter()
+                .filter_map(|r| r.changelog_url.clone())
+                .collect(),
+        },
+        None => BinaryStatus::Installed { version: current },
+    }
+}
+
+/// Whether installing `version` over the active one is an upgrade that can be
+/// rolled back. Replacing an unversioned copy in `bin/` always is.
+fn is_upgrade(active: Option<&str>, version: &str) -> bool {
+    active.is_none_or(|active| compare_versions(version, active) == Ordering::Greater)
+}
+
+/// Order version strings like semver: by their numeric parts ("9.0.10" after
+/// "9.0.2"), with a pre-release ("9.0.2-alpha.1") before its release
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    // Build metadata after `+` does not affect precedence
+    let parts = |v: &str| -> (Vec<u64>, Option<String>) {
+        let v = v.split('+').next().unwrap_or(v);
+        let (core, pre) = match v.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (v, None),
+        };
+        let numbers = core
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|part| part.parse().ok())
+            .collect();
+        (numbers, pre)
+    };
+    let (numbers_a, pre_a) = parts(a);
+    let (numbers_b, pre_b) = parts(b);
+
+    numbers_a
+        .cmp(&numbers_b)
+        .then_with(|| match (pre_a, pre_b) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(pre_a), Some(pre_b)) => compare_pre_releases(&pre_a, &pre_b),
+        })
+        .then_with(|| a.cmp(b))
+}
+
+/// Compare dot-separated pre-release identifiers: numeric ones numerically and
+/// before alphanumeric ones, and a shorter list first when it is a prefix
+fn compare_pre_releases(a: &str, b: &str) -> Ordering {
+    let mut ids_a = a.split('.');
+    let mut ids_b = b.split('.');
+    loop {
+        let ordering = match (ids_a.next(), ids_b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Well-formed signature with the release key's id but a bogus signature
+    const FORGED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWRH90+1Yb+bvgcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc=
+trusted comment: timestamp:1736936625\tfile:checksums.json
+BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBw==
+";
+
+    fn manager(strict_checksums: bool) -> BinaryManager {
+        BinaryManager::from_config(&BinariesConfig {
+            strict_checksums,
+            ..BinariesConfig::default()
+        })
+    }
+
+    /// A release announced by the remote manifest rather than built in
+    fn remote_release() -> BinaryRelease {
+        BinaryRelease {
+            version: "99.0.0".to_string(),
+            url: "https://example.com/releases/espo-99".to_string(),
+            sha256: String::new(),
+            size_bytes: 0,
+            archive_path: None,
+            is_archive: false,
+            changelog_url: None,
+        }
+    }
+
+    fn builtin_release(service: ServiceId) -> BinaryRelease {
+        BinaryManager::get_releases_for_platform()[&service][0].clone()
+    }
+
+    fn is_unverified(result: Result<impl std::fmt::Debug>) -> bool {
+        matches!(result, Err(IsomerError::Unverified { .. }))
+    }
+
+    #[test]
+    fn verify_signature_rejects_malformed_signature() {
+        assert!(is_unverified(BinaryManager::verify_signature(
+            b"{}",
+            "not a signature"
+        )));
+    }
+
+    #[test]
+    fn verify_signature_rejects_forged_signature() {
+        assert!(is_unverified(BinaryManager::verify_signature(
+            b"{}",
+            FORGED_SIGNATURE
+        )));
+    }
+
+    #[test]
+    fn load_checksums_rejects_bad_signature() {
+        let mut manager = manager(false);
+        let content = r#"{"espo-99": "aa"}"#;
+        assert!(is_unverified(
+            manager.load_checksums(content, Some(FORGED_SIGNATURE))
+        ));
+        assert!(manager.checksums_cache.is_none());
+    }
+
+    #[test]
+    fn unsigned_manifest_is_refused_in_strict_mode() {
+        let mut manager = manager(true);
+        manager
+            .load_checksums(r#"{"espo-99": "aa"}"#, None)
+            .unwrap();
+        assert!(!manager.checksums_signed);
+        assert!(is_unverified(manager.expected_checksum(&remote_release())));
+    }
+
+    #[test]
+    fn unsigned_manifest_is_used_outside_strict_mode() {
+        let mut manager = manager(false);
+        manager
+            .load_checksums(r#"{"espo-99": "aa"}"#, None)
+            .unwrap();
+        assert_eq!(
+            manager.expected_checksum(&remote_release()).unwrap(),
+            Some("aa".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_entry_is_refused_in_strict_mode() {
+        let mut manager = manager(true);
+        manager.load_checksums("{}", None).unwrap();
+        manager.checksums_signed = true;
+        assert!(is_unverified(manager.expected_checksum(&remote_release())));
+
+        // Without any manifest as well
+        assert!(is_unverified(
+            self::manager(true).expected_checksum(&remote_release())
+        ));
+    }
+
+    #[test]
+    fn missing_entry_is_skipped_outside_strict_mode() {
+        let manager = manager(false);
+        assert_eq!(manager.expected_checksum(&remote_release()).unwrap(), None);
+    }
+
+    #[test]
+    fn builtin_checksum_is_used_without_manifest() {
+        let release = builtin_release(ServiceId::JsonRpc);
+        assert_eq!(
+            manager(true).expected_checksum(&release).unwrap(),
+            Some(release.sha256.clone())
+        );
+    }
+
+    #[test]
+    fn builtin_release_without_checksum_is_refused_in_strict_mode() {
+        let release = builtin_release(ServiceId::Espo);
+        assert!(release.sha256.is_empty());
+        let filename = downloads::file_name(&release.url);
+
+        let mut manager = manager(true);
+        manager
+            .load_checksums(&format!(r#"{{"{}": "aa"}}"#, filename), None)
+            .unwrap();
+        assert!(is_unverified(manager.expected_checksum(&release)));
+        assert!(is_unverified(
+            self::manager(true).expected_checksum(&release)
+        ));
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert_eq!(compare_versions("9.0.10", "9.0.2"), Ordering::Greater);
+        assert_eq!(compare_versions("28.1", "29.2"), Ordering::Less);
+        assert_eq!(compare_versions("0.22.1", "0.22.1"), Ordering::Equal);
+    }
+
+    #[test]
+    fn pre_release_ranks_below_release() {
+        assert_eq!(compare_versions("9.0.2-alpha.1", "9.0.2"), Ordering::Less);
+        assert_eq!(
+            compare_versions("9.0.2", "9.0.2-alpha.1"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_versions("9.0.3-alpha.1", "9.0.2"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn pre_releases_compare_by_identifier() {
+        assert_eq!(
+            compare_versions("9.0.2-alpha.2", "9.0.2-alpha.10"),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_versions("9.0.2-alpha", "9.0.2-alpha.1"),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_versions("9.0.2-alpha.1", "9.0.2-beta"),
+            Ordering::Less
+        );
+        assert_eq!(compare_versions("9.0.2-1", "9.0.2-alpha"), Ordering::Less);
+        assert_eq!(
+            compare_versions("9.0.2-rc.1", "9.0.2-beta.2"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn build_metadata_is_ignored() {
+        assert_eq!(
+            compare_versions("1.0.0+linux", "1.0.0-rc.1"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn release_after_its_pre_release_is_an_upgrade() {
+        assert!(is_upgrade(Some("9.0.2-alpha.1"), "9.0.2"));
+        assert!(!is_upgrade(Some("9.0.2"), "9.0.2-alpha.1"));
+        assert!(!is_upgrade(Some("29.2"), "28.1"));
+        assert!(!is_upgrade(Some("29.2"), "29.2"));
+        assert!(is_upgrade(None, "29.2"));
+    }
 }
@@ -0,0 +1,367 @@
+//! Async Bitcoin Core JSON-RPC client
+//!
+//! One place that knows how to talk to bitcoind: authentication (user and
+//! password, or the `.cookie` file bitcoind writes when none is configured),
+//! wallet endpoints and error handling. Methods are typed where callers care
+//! about the result; anything else can go through [`BitcoinRpc::call`].
+
+use crate::config::IsomerConfig;
+use crate::profiles::Profile;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Bitcoin Core RPC error codes we react to (see `rpc/protocol.h`)
+pub mod codes {
+    /// Unspecified wallet problem, e.g. "Database already exists"
+    pub const WALLET_ERROR: i64 = -4;
+    pub const WALLET_ALREADY_LOADED: i64 = -35;
+}
+
+/// How requests are authenticated
+#[derive(Debug, Clone)]
+pub enum RpcAuth {
+    UserPass {
+        user: String,
+        password: String,
+    },
+    /// bitcoind's `.cookie` file, re-read on every request since it changes on restart
+    CookieFile(PathBuf),
+}
+
+/// Why an RPC call failed
+#[derive(Debug, Clone)]
+pub enum RpcError {
+    /// bitcoind could not be reached
+    Transport(String),
+    /// Non-JSON HTTP error, e.g. 401 for bad credentials
+    Http { status: u16, body: String },
+    /// bitcoind rejected the call
+    Rpc { code: i64, message: String },
+    /// The cookie file could not be read
+    Auth(String),
+    /// The response did not have the expected shape
+    Parse(String),
+}
+
+impl RpcError {
+    /// The Bitcoin Core error code, if bitcoind returned one
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            RpcError::Rpc { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::Transport(e) => write!(f, "RPC call failed: {}", e),
+            RpcError::Http { status, body } if body.is_empty() => {
+                write!(f, "Bitcoin RPC returned HTTP {}", status)
+            }
+            RpcError::Http { status, body } => {
+                write!(f, "Bitcoin RPC returned HTTP {}: {}", status, body)
+            }
+            RpcError::Rpc { code, message } => {
+                write!(f, "Bitcoin RPC error {}: {}", code, message)
+            }
+            RpcError::Auth(e) => write!(f, "Bitcoin RPC authentication failed: {}", e),
+            RpcError::Parse(e) => write!(f, "Failed to parse response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl From<RpcError> for String {
+    fn from(e: RpcError) -> Self {
+        e.to_string()
+    }
+}
+
+/// `getmempoolinfo`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolInfo {
+    pub size: usize,
+    pub bytes: u64,
+}
+
+/// An entry of `getrawmempool true`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolEntry {
+    pub ancestorcount: u64,
+    pub descendantcount: u64,
+}
+
+/// `getblockheader`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub hash: String,
+    pub height: u64,
+    /// Unix timestamp
+    pub time: u64,
+    pub previousblockhash: Option<String>,
+}
+
+/// `generateblock`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GeneratedBlock {
+    hash: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<serde_json::Value>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+/// Client for a bitcoind node (or one of its wallets)
+#[derive(Debug, Clone)]
+pub struct BitcoinRpc {
+    client: reqwest::Client,
+    url: String,
+    auth: RpcAuth,
+}
+
+impl BitcoinRpc {
+    pub fn new(url: impl Into<String>, auth: RpcAuth) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+            auth,
+        }
+    }
+
+    /// The node Isomer runs, using the configured credentials, or the active
+    /// profile's `.cookie` file when none are set
+    pub fn from_config(config: &IsomerConfig) -> Self {
+        let btc = &config.bitcoind;
+        let auth = if btc.rpc_user.is_empty() || btc.rpc_password.is_empty() {
+            RpcAuth::CookieFile(
+                Profile::active()
+                    .runtime_dir()
+                    .join("bitcoin/regtest/.cookie"),
+            )
+        } else {
+            RpcAuth::UserPass {
+                user: btc.rpc_user.clone(),
+                password: btc.rpc_password.clone(),
+            }
+        };
+        Self::new(
+            format!("http://127.0.0.1:{}", config.ports.bitcoind_rpc),
+            auth,
+        )
+    }
+
+    /// Give up on requests after `timeout` (no limit by default, since mining
+    /// many blocks can take a while)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        self
+    }
+
+    /// A client for the wallet endpoint of a loaded wallet
+    pub fn wallet(&self, name: &str) -> Self {
+        Self {
+            client: self.client.clone(),
+            url: format!("{}/wallet/{}", self.url.trim_end_matches('/'), name),
+            auth: self.auth.clone(),
+        }
+    }
+
+    /// Call any RPC method and deserialize its result
+    pub async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, RpcError> {
+        let (user, password) = self.credentials()?;
+
+        let response = self
+            .client
+            .post(&self.url)
+            .basic_auth(user, Some(password))
+            .json(&serde_json::json!({
+                "jsonrpc": "1.0",
+                "id": "isomer",
+                "method": method,
+                "params": params
+            }))
+            .send()
+            .await
+            .map_err(|e| RpcError::Transport(e.to_string()))?;
+
+        // bitcoind reports RPC errors with a JSON body and a 4xx/5xx status
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| RpcError::Transport(e.to_string()))?;
+        let response: RpcResponse = match serde_json::from_str(&body) {
+            Ok(response) => response,
+            Err(_) if !status.is_success() => {
+                return Err(RpcError::Http {
+                    status: status.as_u16(),
+                    body: body.trim().to_string(),
+                })
+            }
+            Err(e) => return Err(RpcError::Parse(format!("{}: {}", method, e))),
+        };
+
+        if let Some(error) = response.error {
+            return Err(RpcError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        serde_json::from_value(response.result.unwrap_or_default())
+            .map_err(|e| RpcError::Parse(format!("{}: {}", method, e)))
+    }
+
+    fn credentials(&self) -> Result<(String, String), RpcError> {
+        match &self.auth {
+            RpcAuth::UserPass { user, password } => Ok((user.clone(), password.clone())),
+            RpcAuth::CookieFile(path) => {
+                let cookie = std::fs::read_to_string(path)
+                    .map_err(|e| RpcError::Auth(format!("{}: {}", path.display(), e)))?;
+                let (user, password) = cookie
+                    .trim()
+                    .split_once(':')
+                    .ok_or_else(|| RpcError::Auth(format!("{} is malformed", path.display())))?;
+                Ok((user.to_string(), password.to_string()))
+            }
+        }
+    }
+
+    // Chain
+
+    pub async fn get_block_count(&self) -> Result<u64, RpcError> {
+        self.call("getblockcount", serde_json::json!([])).await
+    }
+
+    pub async fn get_best_block_hash(&self) -> Result<String, RpcError> {
+        self.call("getbestblockhash", serde_json::json!([])).await
+    }
+
+    pub async fn get_block_hash(&self, height: u64) -> Result<String, RpcError> {
+        self.call("getblockhash", serde_json::json!([height])).await
+    }
+
+    pub async fn get_block_header(&self, hash: &str) -> Result<BlockHeader, RpcError> {
+        self.call("getblockheader", serde_json::json!([hash])).await
+    }
+
+    pub async fn invalidate_block(&self, hash: &str) -> Result<(), RpcError> {
+        self.call::<serde_json::Value>("invalidateblock", serde_json::json!([hash]))
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn reconsider_block(&self, hash: &str) -> Result<(), RpcError> {
+        self.call::<serde_json::Value>("reconsiderblock", serde_json::json!([hash]))
+            .await
+            .map(|_| ())
+    }
+
+    /// Scan the UTXO set for `scanobjects` (descriptors), returning the raw result
+    pub async fn scan_tx_out_set(
+        &self,
+        descriptors: serde_json::Value,
+    ) -> Result<serde_json::Value, RpcError> {
+        self.call("scantxoutset", serde_json::json!(["start", descriptors]))
+            .await
+    }
+
+    // Mempool
+
+    pub async fn get_mempool_info(&self) -> Result<MempoolInfo, RpcError> {
+        self.call("getmempoolinfo", serde_json::json!([])).await
+    }
+
+    /// Mempool entries by txid
+    pub async fn get_raw_mempool_verbose(&self) -> Result<HashMap<String, MempoolEntry>, RpcError> {
+        self.call("getrawmempool", serde_json::json!([true])).await
+    }
+
+    // Mining
+
+    /// Mine blocks to an address, returning their hashes
+    pub async fn generate_to_address(
+        &self,
+        blocks: u32,
+        address: &str,
+    ) -> Result<Vec<String>, RpcError> {
+        self.call("generatetoaddress", serde_json::json!([blocks, address]))
+            .await
+    }
+
+    /// Mine a block containing exactly `txids` (in order), returning its hash
+    pub async fn generate_block(
+        &self,
+        address: &str,
+        txids: &[String],
+    ) -> Result<String, RpcError> {
+        self.call::<GeneratedBlock>("generateblock", serde_json::json!([address, txids]))
+            .await
+            .map(|block| block.hash)
+    }
+
+    // Wallets
+
+    pub async fn list_wallets(&self) -> Result<Vec<String>, RpcError> {
+        self.call("listwallets", serde_json::json!([])).await
+    }
+
+    pub async fn create_wallet(&self, name: &str) -> Result<(), RpcError> {
+        self.call::<serde_json::Value>("createwallet", serde_json::json!([name]))
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn load_wallet(&self, name: &str) -> Result<(), RpcError> {
+        self.call::<serde_json::Value>("loadwallet", serde_json::json!([name]))
+            .await
+            .map(|_| ())
+    }
+
+    /// Wallet endpoint only
+    pub async fn get_new_address(&self, address_type: &str) -> Result<String, RpcError> {
+        self.call("getnewaddress", serde_json::json!(["", address_type]))
+            .await
+    }
+
+    /// Wallet endpoint only; balance in BTC
+    pub async fn get_balance(&self) -> Result<f64, RpcError> {
+        self.call("getbalance", serde_json::json!([])).await
+    }
+
+    /// Wallet endpoint only; returns the txid
+    pub async fn send_to_address(
+        &self,
+        address: &str,
+        amount_btc: f64,
+    ) -> Result<String, RpcError> {
+        self.call("sendtoaddress", serde_json::json!([address, amount_btc]))
+            .await
+    }
+
+    /// Wallet endpoint only; pays several addresses (amounts in BTC) in one transaction
+    pub async fn send_many(&self, amounts: &HashMap<String, f64>) -> Result<String, RpcError> {
+        self.call("sendmany", serde_json::json!(["", amounts]))
+            .await
+    }
+}
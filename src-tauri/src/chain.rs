@@ -0,0 +1,72 @@
+//! Chain operations against the regtest Bitcoin Core node
+//!
+//! Shared by the Tauri command handlers and the headless CLI
+
+use crate::bitcoin_rpc::BitcoinRpc;
+use crate::config::IsomerConfig;
+use crate::error::{IsomerError, Result};
+
+/// Fallback mining address used when no account is available
+pub const DEFAULT_MINING_ADDRESS: &str = "bcrt1q9zuctyd46l7sdedccdk47335lzsmjz2wngdv3u";
+
+/// Live chain information reported by bitcoind
+#[derive(Debug, Clone, Default)]
+pub struct ChainInfo {
+    pub block_height: u64,
+    pub mempool_size: usize,
+}
+
+/// Fetch block height and mempool size, returning defaults for anything unreachable
+pub async fn get_chain_info(config: &IsomerConfig) -> ChainInfo {
+    let rpc = BitcoinRpc::from_config(config).with_timeout(std::time::Duration::from_millis(500));
+
+    ChainInfo {
+        block_height: rpc.get_block_count().await.unwrap_or_default(),
+        mempool_size: rpc
+            .get_mempool_info()
+            .await
+            .map(|info| info.size)
+            .unwrap_or_default(),
+    }
+}
+
+/// Current block height
+pub async fn get_block_count(config: &IsomerConfig) -> Result<u64> {
+    Ok(BitcoinRpc::from_config(config).get_block_count().await?)
+}
+
+/// Send BTC from the dev wallet to an address, returning the txid
+pub async fn faucet(config: &IsomerConfig, address: &str, amount: f64) -> Result<String> {
+    // Default to 1 BTC if not specified or 0
+    let amount_btc = if amount <= 0.0 { 1.0 } else { amount };
+
+    // Send from dev wallet to the target address
+    let txid = BitcoinRpc::from_config(config)
+        .wallet("dev")
+        .send_to_address(address, amount_btc)
+        .await?;
+
+    tracing::info!(
+        "Faucet: sent {} BTC to {} (txid: {})",
+        amount_btc,
+        address,
+        txid
+    );
+
+    Ok(txid)
+}
+
+/// Mine blocks to an address, returning the new block height
+pub async fn mine_blocks(config: &IsomerConfig, count: u32, address: &str) -> Result<u64> {
+    if count > 1000 {
+        return Err(IsomerError::invalid(
+            "Cannot mine more than 1000 blocks at once.",
+        ));
+    }
+
+    let rpc = BitcoinRpc::from_config(config);
+    rpc.generate_to_address(count, address).await?;
+
+    // Get new block height
+    Ok(rpc.get_block_count().await?)
+}
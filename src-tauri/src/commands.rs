@@ -2,11 +2,27 @@
 //!
 //! These are the functions exposed to the frontend via Tauri's invoke system
 
-use crate::binary_manager::{BinaryInfo, BinaryManager};
-use crate::config::IsomerConfig;
-use crate::process_manager::ServiceId;
+use crate::accounts;
+use crate::binary_manager::{
+    BinaryInfo, BinaryManager, BinaryVersion, ReleaseManifest, ALKANES_WASM_ID,
+};
+use crate::chain;
+use crate::config::{IsomerConfig, MiningConfig};
+use crate::error::{IsomerError, Result};
+use crate::indexers::{self, IndexerStatus};
+use crate::jobs::{run_blocking, Job, JobKind, Reporter};
+use crate::log_files::{self, LogSearch};
+use crate::log_stream::{LogFilter, LogSubscription};
+use crate::miner::AutoMineStatus;
+use crate::process_manager::{PortConflict, ProcessManager, ServiceId};
+use crate::profiles::{self, Profile, ProfileInfo};
+use crate::reindex::{self, ReindexRequest};
+use crate::reorg::{self, ReorgReport, ReorgRequest};
+use crate::snapshots::{self, SnapshotInfo};
 use crate::state::{Account, AppState, ServiceStatus, SystemStatus};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::{Emitter, State};
 use tokio::sync::RwLock;
 
@@ -14,9 +30,9 @@ type SharedState = Arc<RwLock<AppState>>;
 
 /// Get the current system status
 #[tauri::command]
-pub async fn get_status(state: State<'_, SharedState>) -> Result<SystemStatus, String> {
+pub async fn get_status(state: State<'_, SharedState>) -> Result<SystemStatus> {
     // 1. Get process status from state
-    let mut state_guard = state.write().await;
+    let state_guard = state.read().await;
     let mut system_status = state_guard.get_status();
     let config = state_guard.config.clone();
     drop(state_guard); // Release lock
@@ -29,84 +45,306 @@ pub async fn get_status(state: State<'_, SharedState>) -> Result<SystemStatus, S
         .any(|s| s.id == "bitcoind" && s.status == "running");
 
     if bitcoind_running {
-        let rpc_url = format!("http://127.0.0.1:{}", config.ports.bitcoind_rpc);
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_millis(500))
-            .build()
-            .unwrap_or_default();
-
-        // Get Block Count
-        let count_req = client
-            .post(&rpc_url)
-            .basic_auth(
-                &config.bitcoind.rpc_user,
-                Some(&config.bitcoind.rpc_password),
-            )
-            .json(&serde_json::json!({
-                "jsonrpc": "1.0",
-                "id": "isomer-status",
-                "method": "getblockcount",
-                "params": []
-            }));
-
-        if let Ok(res) = count_req.send().await {
-            if let Ok(json) = res.json::<serde_json::Value>().await {
-                if let Some(height) = json.get("result").and_then(|h| h.as_u64()) {
-                    system_status.block_height = height;
-                }
+        let info = chain::get_chain_info(&config).await;
+        system_status.block_height = info.block_height;
+        system_status.mempool_size = info.mempool_size;
+
+        // 3. Compare running indexers against bitcoind's height
+        let running: Vec<ServiceId> = indexers::INDEXERS
+            .into_iter()
+            .filter(|s| {
+                system_status
+                    .services
+                    .iter()
+                    .any(|info| info.id == s.id() && info.status == "running")
+            })
+            .collect();
+        system_status.indexers = indexers::get_tips(&running, &config)
+            .await
+            .into_iter()
+            .map(|tip| IndexerStatus::new(tip, info.block_height))
+            .collect();
+    }
+
+    Ok(system_status)
+}
+
+/// Register a job and run `work` in the background on a handle to the
+/// process manager, so the state lock is free while services start or stop
+async fn spawn_job<F, Fut>(
+    state: &SharedState,
+    kind: JobKind,
+    service: Option<ServiceId>,
+    work: F,
+) -> Result<Job>
+where
+    F: FnOnce(ProcessManager, Reporter) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+{
+    let state = state.read().await;
+    let job = state.jobs.begin(kind, service)?;
+    let jobs = state.jobs.clone();
+    let manager = state.process_manager.handle();
+    drop(state);
+
+    let id = job.id;
+    tokio::spawn(async move {
+        let result = work(manager, jobs.reporter(id)).await;
+        jobs.finish(id, result);
+    });
+    Ok(job)
+}
+
+/// Start all services as a background job
+#[tauri::command]
+pub async fn start_services(state: State<'_, SharedState>) -> Result<Job> {
+    let shared = state.inner().clone();
+    spawn_job(
+        state.inner(),
+        JobKind::StartAll,
+        None,
+        |manager, report| async move {
+            manager
+                .start_with_progress(&ServiceId::all(), &*report)
+                .await?;
+
+            let mut state = shared.write().await;
+            if state.config.mining.auto_mine {
+                state.start_auto_miner();
             }
-        }
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Stop all services as a background job
+#[tauri::command]
+pub async fn stop_services(state: State<'_, SharedState>) -> Result<Job> {
+    let shared = state.inner().clone();
+    spawn_job(
+        state.inner(),
+        JobKind::StopAll,
+        None,
+        |manager, report| async move {
+            shared.write().await.miner.stop();
+            run_blocking(move || manager.stop_all_with_progress(&*report)).await
+        },
+    )
+    .await
+}
 
-        // Get Mempool Info
-        let mempool_req = client
-            .post(&rpc_url)
-            .basic_auth(
-                &config.bitcoind.rpc_user,
-                Some(&config.bitcoind.rpc_password),
-            )
-            .json(&serde_json::json!({
-                "jsonrpc": "1.0",
-                "id": "isomer-status",
-                "method": "getmempoolinfo",
-                "params": []
-            }));
-
-        if let Ok(res) = mempool_req.send().await {
-            if let Ok(json) = res.json::<serde_json::Value>().await {
-                if let Some(size) = json
-                    .get("result")
-                    .and_then(|r| r.get("size"))
-                    .and_then(|s| s.as_u64())
-                {
-                    system_status.mempool_size = size as usize;
-                }
+/// Start a single service, starting any missing dependencies first
+#[tauri::command]
+pub async fn start_service(service: ServiceId, state: State<'_, SharedState>) -> Result<Job> {
+    spawn_job(
+        state.inner(),
+        JobKind::StartService,
+        Some(service),
+        move |manager, report| async move { manager.start_with_progress(&[service], &*report).await },
+    )
+    .await
+}
+
+/// Stop a single service and everything that depends on it
+#[tauri::command]
+pub async fn stop_service(service: ServiceId, state: State<'_, SharedState>) -> Result<Job> {
+    spawn_job(
+        state.inner(),
+        JobKind::StopService,
+        Some(service),
+        move |manager, _| async move {
+            run_blocking(move || manager.stop_with_dependents(service))
+                .await
+                .map(|_| ())
+        },
+    )
+    .await
+}
+
+/// Restart a single service along with its running dependents
+#[tauri::command]
+pub async fn restart_service(service: ServiceId, state: State<'_, SharedState>) -> Result<Job> {
+    spawn_job(
+        state.inner(),
+        JobKind::RestartService,
+        Some(service),
+        move |manager, report| async move {
+            manager
+                .restart_with_progress(service, &*report)
+                .await
+                .map(|_| ())
+        },
+    )
+    .await
+}
+
+/// Reset chain - stops services and clears all data, as a background job
+#[tauri::command]
+pub async fn reset_chain(state: State<'_, SharedState>) -> Result<Job> {
+    let shared = state.inner().clone();
+    spawn_job(
+        state.inner(),
+        JobKind::Reset,
+        None,
+        |manager, report| async move {
+            shared.write().await.miner.stop();
+            run_blocking(move || manager.reset_data_with_progress(&*report)).await
+        },
+    )
+    .await
+}
+
+/// Swap the alkanes.wasm Metashrew loads and rebuild its index (wiped, or
+/// truncated to a height), as a background job reporting reindex progress
+#[tauri::command]
+pub async fn reindex_metashrew(
+    request: ReindexRequest,
+    state: State<'_, SharedState>,
+) -> Result<Job> {
+    let shared = state.inner().clone();
+    spawn_job(
+        state.inner(),
+        JobKind::Reindex,
+        Some(ServiceId::Metashrew),
+        move |mut manager, report| async move {
+            reindex::reindex(&mut manager, &request, &*report).await?;
+
+            // A new module becomes the profile's alkanes.wasm override
+            if request.wasm.is_some() {
+                let mut state = shared.write().await;
+                let mut config = state.config.clone();
+                config.binaries.wasm_paths = manager.config().binaries.wasm_paths.clone();
+                state.process_manager.update_config(config.clone());
+                state.config = config;
             }
-        }
-    }
+            Ok(())
+        },
+    )
+    .await
+}
 
-    Ok(system_status)
+/// Get a background job by id
+#[tauri::command]
+pub async fn get_job(id: u64, state: State<'_, SharedState>) -> Result<Job> {
+    state
+        .read()
+        .await
+        .jobs
+        .get(id)
+        .ok_or_else(|| IsomerError::invalid(format!("Unknown job {}", id)))
 }
 
-/// Start all services
+/// Recent background jobs, newest first
 #[tauri::command]
-pub async fn start_services(state: State<'_, SharedState>) -> Result<(), String> {
-    let mut state = state.write().await;
-    let config = state.config.clone();
-    state.process_manager.start_all(&config)
+pub async fn list_jobs(state: State<'_, SharedState>) -> Result<Vec<Job>> {
+    Ok(state.read().await.jobs.list())
 }
 
-/// Stop all services
+/// Snapshot the chain data and config of the active profile, as a background job.
+///
+/// Services are stopped while the data is archived and started again afterwards.
 #[tauri::command]
-pub async fn stop_services(state: State<'_, SharedState>) -> Result<(), String> {
-    let mut state = state.write().await;
-    state.process_manager.stop_all()
+pub async fn create_snapshot(
+    name: String,
+    output: Option<String>,
+    state: State<'_, SharedState>,
+) -> Result<Job> {
+    let shared = state.inner().clone();
+    spawn_job(
+        state.inner(),
+        JobKind::CreateSnapshot,
+        None,
+        move |manager, report| async move {
+            let manager = Arc::new(manager);
+            let config = manager.config().clone();
+            let block_height = if manager.is_running(ServiceId::Bitcoind) {
+                Some(chain::get_chain_info(&config).await.block_height)
+            } else {
+                None
+            };
+
+            let running = manager.running_services();
+            let was_mining = {
+                let mut state = shared.write().await;
+                let was_mining = state.miner.is_running();
+                state.miner.stop();
+                was_mining
+            };
+            let stopping = manager.clone();
+            run_blocking(move || stopping.stop_all_with_progress(&*report)).await?;
+
+            let profile = manager.profile().clone();
+            let result = run_blocking(move || {
+                snapshots::create(
+                    &profile,
+                    &config,
+                    &name,
+                    block_height,
+                    output.as_deref().map(std::path::Path::new),
+                )
+            })
+            .await;
+
+            // Bring the stack back even if archiving failed
+            if !running.is_empty() {
+                manager.start_with_dependencies(&running).await?;
+            }
+            if was_mining {
+                shared.write().await.start_auto_miner();
+            }
+            result.map(|_| ())
+        },
+    )
+    .await
 }
 
-/// Reset chain - stops services and clears all data
+/// List snapshots in the snapshots directory
 #[tauri::command]
-pub async fn reset_chain(state: State<'_, SharedState>) -> Result<(), String> {
-    let mut state = state.write().await;
-    state.process_manager.reset_data()
+pub async fn list_snapshots() -> Result<Vec<SnapshotInfo>> {
+    run_blocking(|| Ok(snapshots::list())).await
+}
+
+/// Stop all services and replace the active profile's chain data with a
+/// snapshot, as a background job. `snapshot` is a snapshot name or a path to a
+/// snapshot file.
+#[tauri::command]
+pub async fn restore_snapshot(snapshot: String, state: State<'_, SharedState>) -> Result<Job> {
+    let path = snapshots::resolve(&snapshot);
+    let info = snapshots::info(&path)?;
+
+    let shared = state.inner().clone();
+    spawn_job(
+        state.inner(),
+        JobKind::RestoreSnapshot,
+        None,
+        move |manager, report| async move {
+            shared.write().await.miner.stop();
+            let profile = manager.profile().clone();
+            let current = manager.config().clone();
+            let (_, config) = run_blocking(move || {
+                manager.stop_all_with_progress(&*report)?;
+                snapshots::restore(&profile, &current, &path)
+            })
+            .await?;
+
+            // Only the config swap needs the state
+            let mut state = shared.write().await;
+            state.process_manager.profile().save_config(&config)?;
+            state.accounts = accounts::accounts_for_config(&config);
+            state.process_manager.update_config(config.clone());
+            state.config = config;
+            state.block_height = info.manifest.block_height.unwrap_or(0);
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Delete a snapshot by name or path
+#[tauri::command]
+pub async fn delete_snapshot(snapshot: String) -> Result<()> {
+    snapshots::delete(&snapshot)
 }
 
 /// Get service logs
@@ -115,85 +353,85 @@ pub async fn get_logs(
     service: Option<String>,
     limit: Option<usize>,
     state: State<'_, SharedState>,
-) -> Result<Vec<crate::process_manager::LogEntry>, String> {
+) -> Result<Vec<crate::process_manager::LogEntry>> {
     let state = state.read().await;
     Ok(state
         .process_manager
         .get_logs(service, limit.unwrap_or(500)))
 }
 
-/// Clear all logs
+/// Subscribe to live logs. Matching entries are pushed as batched `log-entry`
+/// events; pass the last seen `cursor` to resume without gaps.
 #[tauri::command]
-pub async fn clear_logs(state: State<'_, SharedState>) -> Result<(), String> {
+pub async fn subscribe_logs(
+    filter: Option<LogFilter>,
+    cursor: Option<u64>,
+    state: State<'_, SharedState>,
+) -> Result<LogSubscription> {
     let state = state.read().await;
-    state.process_manager.clear_logs();
+    Ok(state
+        .process_manager
+        .subscribe_logs(filter.unwrap_or_default(), cursor))
+}
+
+/// Cancel a log subscription
+#[tauri::command]
+pub async fn unsubscribe_logs(id: u64, state: State<'_, SharedState>) -> Result<()> {
+    let state = state.read().await;
+    state.process_manager.unsubscribe_logs(id);
     Ok(())
 }
 
-/// Faucet - send BTC from dev wallet to any address
+/// Read service logs from the persistent log files, including rotated ones
+/// from earlier sessions. `since`/`until` are Unix timestamps in seconds.
 #[tauri::command]
-pub async fn faucet(
-    address: String,
-    amount: f64,
+pub async fn read_log_history(
+    service: Option<String>,
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: Option<usize>,
     state: State<'_, SharedState>,
-) -> Result<String, String> {
-    let state = state.read().await;
-    let config = &state.config;
-
-    // Default to 1 BTC if not specified or 0
-    let amount_btc = if amount <= 0.0 { 1.0 } else { amount };
-
-    let rpc_url = format!("http://127.0.0.1:{}", config.ports.bitcoind_rpc);
-    let wallet_rpc_url = format!("{}/wallet/dev", rpc_url);
-
-    let client = reqwest::Client::new();
-
-    // Send from dev wallet to the target address
-    let response = client
-        .post(&wallet_rpc_url)
-        .basic_auth(
-            &config.bitcoind.rpc_user,
-            Some(&config.bitcoind.rpc_password),
-        )
-        .json(&serde_json::json!({
-            "jsonrpc": "1.0",
-            "id": "isomer",
-            "method": "sendtoaddress",
-            "params": [address, amount_btc]
-        }))
-        .send()
-        .await
-        .map_err(|e| format!("RPC call failed: {}", e))?;
+) -> Result<Vec<crate::process_manager::LogEntry>> {
+    let services = match service {
+        Some(service) => vec![service.parse::<ServiceId>()?],
+        None => ServiceId::all(),
+    };
+    let logs_dir = state.read().await.process_manager.profile().logs_dir();
+
+    // File reads can be large; keep them off the async runtime
+    tokio::task::spawn_blocking(move || {
+        log_files::read_history_all(&logs_dir, &services, since, until, limit.unwrap_or(1000))
+    })
+    .await
+    .map_err(|e| IsomerError::io("Failed to read log history", e))
+}
 
-    let result: serde_json::Value = response
-        .json()
+/// Search persisted logs by regex, level, service and time range
+#[tauri::command]
+pub async fn search_logs(
+    query: LogSearch,
+    state: State<'_, SharedState>,
+) -> Result<Vec<crate::process_manager::LogEntry>> {
+    let logs_dir = state.read().await.process_manager.profile().logs_dir();
+    let entries = tokio::task::spawn_blocking(move || log_files::search(&logs_dir, &query))
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    if let Some(error) = result.get("error").and_then(|e| e.as_object()) {
-        return Err(format!(
-            "Faucet error: {}",
-            error
-                .get("message")
-                .and_then(|m| m.as_str())
-                .unwrap_or("unknown")
-        ));
-    }
-
-    let txid = result
-        .get("result")
-        .and_then(|r| r.as_str())
-        .unwrap_or("unknown")
-        .to_string();
+        .map_err(|e| IsomerError::io("Failed to search logs", e))??;
+    Ok(entries)
+}
 
-    tracing::info!(
-        "Faucet: sent {} BTC to {} (txid: {})",
-        amount_btc,
-        address,
-        txid
-    );
+/// Clear all logs
+#[tauri::command]
+pub async fn clear_logs(state: State<'_, SharedState>) -> Result<()> {
+    let state = state.read().await;
+    state.process_manager.clear_logs();
+    Ok(())
+}
 
-    Ok(txid)
+/// Faucet - send BTC from dev wallet to any address
+#[tauri::command]
+pub async fn faucet(address: String, amount: f64, state: State<'_, SharedState>) -> Result<String> {
+    let state = state.read().await;
+    chain::faucet(&state.config, &address, amount).await
 }
 
 /// Mine a specified number of blocks
@@ -202,13 +440,8 @@ pub async fn mine_blocks(
     count: u32,
     address: Option<String>,
     state: State<'_, SharedState>,
-) -> Result<u64, String> {
+) -> Result<u64> {
     let state = state.read().await;
-    let config = &state.config;
-
-    if count > 1000 {
-        return Err("Cannot mine more than 1000 blocks at once.".to_string());
-    }
 
     // Use first account address if none specified
     let mine_to = address.unwrap_or_else(|| {
@@ -216,95 +449,203 @@ pub async fn mine_blocks(
             .accounts
             .first()
             .map(|a| a.address.clone())
-            .unwrap_or_else(|| "bcrt1q9zuctyd46l7sdedccdk47335lzsmjz2wngdv3u".to_string())
+            .unwrap_or_else(|| chain::DEFAULT_MINING_ADDRESS.to_string())
     });
 
-    // Call bitcoin-cli to mine blocks
-    let rpc_url = format!("http://127.0.0.1:{}", config.ports.bitcoind_rpc);
-
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&rpc_url)
-        .basic_auth(
-            &config.bitcoind.rpc_user,
-            Some(&config.bitcoind.rpc_password),
-        )
-        .json(&serde_json::json!({
-            "jsonrpc": "1.0",
-            "id": "isomer",
-            "method": "generatetoaddress",
-            "params": [count, mine_to]
-        }))
-        .send()
-        .await
-        .map_err(|e| format!("RPC call failed: {}", e))?;
+    chain::mine_blocks(&state.config, count, &mine_to).await
+}
 
-    let result: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-    if let Some(error) = result.get("error").and_then(|e| e.as_object()) {
-        return Err(format!(
-            "Bitcoin RPC error: {}",
-            error
-                .get("message")
-                .and_then(|m| m.as_str())
-                .unwrap_or("unknown")
-        ));
+/// Wait until every running indexer has indexed `height` (bitcoind's
+/// current height if unset)
+#[tauri::command]
+pub async fn wait_for_sync(
+    height: Option<u64>,
+    timeout_secs: Option<u64>,
+    state: State<'_, SharedState>,
+) -> Result<Vec<IndexerStatus>> {
+    let state_guard = state.read().await;
+    let config = state_guard.config.clone();
+    let running: Vec<ServiceId> = indexers::INDEXERS
+        .into_iter()
+        .filter(|s| state_guard.process_manager.is_running(*s))
+        .collect();
+    drop(state_guard);
+
+    let height = match height {
+        Some(height) => height,
+        None => chain::get_block_count(&config).await?,
+    };
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(indexers::DEFAULT_SYNC_TIMEOUT_SECS));
+    indexers::wait_for_sync(&config, &running, height, timeout).await
+}
+
+/// Replace the last blocks with a competing branch and report whether the
+/// running indexers followed it
+#[tauri::command]
+pub async fn reorg(request: ReorgRequest, state: State<'_, SharedState>) -> Result<ReorgReport> {
+    // Pause the auto-miner so it does not extend either branch mid-reorg
+    let mut state_guard = state.write().await;
+    let was_mining = state_guard.miner.is_running();
+    state_guard.miner.stop();
+
+    let config = state_guard.config.clone();
+    let address = state_guard
+        .accounts
+        .first()
+        .map(|a| a.address.clone())
+        .unwrap_or_else(|| chain::DEFAULT_MINING_ADDRESS.to_string());
+    let indexers: Vec<ServiceId> = indexers::INDEXERS
+        .into_iter()
+        .filter(|s| state_guard.process_manager.is_running(*s))
+        .collect();
+    drop(state_guard);
+
+    // Waiting for indexers can take a while, so don't hold the state lock
+    let result = reorg::reorg(&config, &request, &address, &indexers).await;
+
+    let mut state_guard = state.write().await;
+    if was_mining {
+        state_guard.start_auto_miner();
     }
+    let report = result?;
+    state_guard.block_height = report.new_tip.height;
+    state_guard.emit("chain-reorg", report.clone());
+    Ok(report)
+}
 
-    // Get new block height
-    let height_response = client
-        .post(&rpc_url)
-        .basic_auth(
-            &config.bitcoind.rpc_user,
-            Some(&config.bitcoind.rpc_password),
-        )
-        .json(&serde_json::json!({
-            "jsonrpc": "1.0",
-            "id": "isomer",
-            "method": "getblockcount",
-            "params": []
-        }))
-        .send()
-        .await
-        .map_err(|e| format!("RPC call failed: {}", e))?;
+/// Start the background auto-miner with the current mining settings
+#[tauri::command]
+pub async fn start_auto_mine(state: State<'_, SharedState>) -> Result<AutoMineStatus> {
+    let mut state = state.write().await;
+    state.start_auto_miner();
+    Ok(state.miner.status(&state.config.mining))
+}
 
-    let height_result: serde_json::Value = height_response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+/// Stop the background auto-miner
+#[tauri::command]
+pub async fn stop_auto_mine(state: State<'_, SharedState>) -> Result<AutoMineStatus> {
+    let mut state = state.write().await;
+    state.miner.stop();
+    Ok(state.miner.status(&state.config.mining))
+}
+
+/// Save new mining settings and apply them to the auto-miner
+#[tauri::command]
+pub async fn configure_auto_mine(
+    settings: MiningConfig,
+    state: State<'_, SharedState>,
+) -> Result<AutoMineStatus> {
+    let mut state = state.write().await;
+    state.config.mining = settings;
+    state
+        .config
+        .save()
+        .map_err(|e| IsomerError::io("Failed to save config", e))?;
+
+    let should_run = state.config.mining.auto_mine
+        && (state.miner.is_running() || state.process_manager.is_running(ServiceId::Bitcoind));
+    if should_run {
+        // Restart so the task picks up the new mode and interval
+        state.start_auto_miner();
+    } else {
+        state.miner.stop();
+    }
 
-    let new_height = height_result
-        .get("result")
-        .and_then(|r| r.as_u64())
-        .unwrap_or(0);
+    Ok(state.miner.status(&state.config.mining))
+}
 
-    Ok(new_height)
+/// Get the auto-miner state
+#[tauri::command]
+pub async fn get_auto_mine_status(state: State<'_, SharedState>) -> Result<AutoMineStatus> {
+    let state = state.read().await;
+    Ok(state.miner.status(&state.config.mining))
 }
 
 /// Get all pre-funded accounts
 #[tauri::command]
-pub async fn get_accounts(state: State<'_, SharedState>) -> Result<Vec<Account>, String> {
+pub async fn get_accounts(state: State<'_, SharedState>) -> Result<Vec<Account>> {
     let state = state.read().await;
-    Ok(state.accounts.clone())
+    let mut accounts = state.accounts.clone();
+    let config = state.config.clone();
+    drop(state);
+
+    // Balances are best-effort; bitcoind may not be running yet
+    if let Err(e) = accounts::refresh_balances(&config, &mut accounts).await {
+        tracing::debug!("Could not refresh account balances: {}", e);
+    }
+
+    Ok(accounts)
 }
 
 /// Check status of all binaries
 #[tauri::command]
-pub async fn check_binaries() -> Result<Vec<BinaryInfo>, String> {
-    let manager = BinaryManager::new();
+pub async fn check_binaries(state: State<'_, SharedState>) -> Result<Vec<BinaryInfo>> {
+    let manager = binary_manager(&state).await;
     Ok(manager.check_all())
 }
 
+/// Install the newest release of a service or of alkanes.wasm (`alkanes-wasm`),
+/// keeping the current one for rollback
+#[tauri::command]
+pub async fn upgrade_binary(
+    app: tauri::AppHandle,
+    state: State<'_, SharedState>,
+    service: String,
+) -> Result<String> {
+    let mut manager = binary_manager(&state).await;
+    manager.apply_updates(&release_manifest(&state).await?);
+    if service == ALKANES_WASM_ID {
+        ensure_stopped(&state, ServiceId::Metashrew).await?;
+        return manager.upgrade_alkanes_wasm().await;
+    }
+    let service: ServiceId = service.parse()?;
+    manager
+        .upgrade(service, move |progress| {
+            let _ = app.emit(
+                "download-progress",
+                serde_json::json!({
+                    "service": service.display_name(),
+                    "progress": progress
+                }),
+            );
+        })
+        .await
+}
+
+/// Undo the last upgrade of a service or of alkanes.wasm (`alkanes-wasm`),
+/// returning the version active again
+#[tauri::command]
+pub async fn rollback_binary(
+    service: String,
+    state: State<'_, SharedState>,
+) -> Result<Option<String>> {
+    if service == ALKANES_WASM_ID {
+        ensure_stopped(&state, ServiceId::Metashrew).await?;
+        return BinaryManager::rollback_alkanes_wasm().await;
+    }
+    let service: ServiceId = service.parse()?;
+    ensure_stopped(&state, service).await?;
+    BinaryManager::rollback(service)
+}
+
+/// Fail unless the service is stopped and no job could start it meanwhile,
+/// before replacing what it runs
+async fn ensure_stopped(state: &SharedState, service: ServiceId) -> Result<()> {
+    let state = state.read().await;
+    state.jobs.ensure_idle()?;
+    if state.process_manager.is_running(service) {
+        return Err(IsomerError::ServiceAlreadyRunning { service });
+    }
+    Ok(())
+}
+
 /// Download missing binaries
 #[tauri::command]
-pub async fn download_binaries(app: tauri::AppHandle) -> Result<(), String> {
-    let mut manager = BinaryManager::new();
+pub async fn download_binaries(app: tauri::AppHandle, state: State<'_, SharedState>) -> Result<()> {
+    let mut manager = binary_manager(&state).await;
 
     // First download alkanes.wasm for metashrew
-    BinaryManager::download_alkanes_wasm().await?;
+    manager.download_alkanes_wasm().await?;
 
     // Then download all service binaries
     manager
@@ -320,42 +661,273 @@ pub async fn download_binaries(app: tauri::AppHandle) -> Result<(), String> {
         .await
 }
 
+/// Install missing binaries from an offline bundle directory or .tar.gz
+#[tauri::command]
+pub async fn install_binaries_from_bundle(
+    app: tauri::AppHandle,
+    state: State<'_, SharedState>,
+    path: String,
+) -> Result<()> {
+    let mut manager = binary_manager(&state).await;
+    manager
+        .install_from_bundle(std::path::Path::new(&path), move |service, progress| {
+            let _ = app.emit(
+                "download-progress",
+                serde_json::json!({
+                    "service": service.display_name(),
+                    "progress": progress
+                }),
+            );
+        })
+        .await
+}
+
 /// Download just the alkanes.wasm file
 #[tauri::command]
-pub async fn download_wasm() -> Result<(), String> {
-    BinaryManager::download_alkanes_wasm().await
+pub async fn download_wasm(state: State<'_, SharedState>) -> Result<()> {
+    binary_manager(&state).await.download_alkanes_wasm().await
+}
+
+/// Known and installed versions of a service binary
+#[tauri::command]
+pub async fn list_binary_versions(
+    service: ServiceId,
+    state: State<'_, SharedState>,
+) -> Result<Vec<BinaryVersion>> {
+    Ok(binary_manager(&state).await.list_versions(service))
+}
+
+/// Download a version of a service binary next to the installed ones
+#[tauri::command]
+pub async fn install_binary_version(
+    app: tauri::AppHandle,
+    state: State<'_, SharedState>,
+    service: ServiceId,
+    version: String,
+) -> Result<()> {
+    let mut manager = binary_manager(&state).await;
+    manager
+        .install_version(service, &version, move |progress| {
+            let _ = app.emit(
+                "download-progress",
+                serde_json::json!({
+                    "service": service.display_name(),
+                    "progress": progress
+                }),
+            );
+        })
+        .await
+}
+
+/// Pin the version a service runs in the active profile, or unpin it with `null`
+#[tauri::command]
+pub async fn set_binary_version(
+    service: ServiceId,
+    version: Option<String>,
+    state: State<'_, SharedState>,
+) -> Result<()> {
+    let mut state = state.write().await;
+    let mut config = state.config.clone();
+    BinaryManager::pin_version(&mut config.binaries, service, version)?;
+    config
+        .save()
+        .map_err(|e| IsomerError::io("Failed to save config", e))?;
+    state.process_manager.update_config(config.clone());
+    state.config = config;
+    Ok(())
+}
+
+/// Run a locally built binary for a service in the active profile, or go back
+/// to the installed one with `null`
+#[tauri::command]
+pub async fn set_binary_path(
+    service: ServiceId,
+    path: Option<String>,
+    state: State<'_, SharedState>,
+) -> Result<()> {
+    let mut state = state.write().await;
+    let mut config = state.config.clone();
+    BinaryManager::from_config(&config.binaries).set_binary_override(
+        &mut config.binaries,
+        service,
+        path.map(PathBuf::from),
+    )?;
+    config
+        .save()
+        .map_err(|e| IsomerError::io("Failed to save config", e))?;
+    state.process_manager.update_config(config.clone());
+    state.config = config;
+    Ok(())
+}
+
+/// Load a locally built alkanes.wasm for a service in the active profile, or go
+/// back to the downloaded one with `null`
+#[tauri::command]
+pub async fn set_wasm_path(
+    service: ServiceId,
+    path: Option<String>,
+    state: State<'_, SharedState>,
+) -> Result<()> {
+    let mut state = state.write().await;
+    let mut config = state.config.clone();
+    BinaryManager::set_wasm_override(&mut config.binaries, service, path.map(PathBuf::from))?;
+    config
+        .save()
+        .map_err(|e| IsomerError::io("Failed to save config", e))?;
+    state.process_manager.update_config(config.clone());
+    state.config = config;
+    Ok(())
+}
+
+/// How long a fetched release manifest is reused before checking again
+const RELEASE_MANIFEST_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Binary manager using the active profile's versions and checksum settings,
+/// with newer releases included if update checks are enabled
+async fn binary_manager(state: &SharedState) -> BinaryManager {
+    let config = state.read().await.config.binaries.clone();
+    let mut manager = BinaryManager::from_config(&config);
+    if config.check_updates {
+        match release_manifest(state).await {
+            Ok(manifest) => manager.apply_updates(&manifest),
+            Err(e) => tracing::warn!("Update check failed: {}", e),
+        }
+    }
+    manager
+}
+
+/// The remote release manifest, fetched at most once per `RELEASE_MANIFEST_TTL`
+async fn release_manifest(state: &SharedState) -> Result<ReleaseManifest> {
+    if let Some((fetched_at, manifest)) = &state.read().await.release_manifest {
+        if fetched_at.elapsed() < RELEASE_MANIFEST_TTL {
+            return Ok(manifest.clone());
+        }
+    }
+    let manifest = BinaryManager::fetch_release_manifest().await?;
+    state.write().await.release_manifest = Some((Instant::now(), manifest.clone()));
+    Ok(manifest)
 }
 
 /// Get current configuration
 #[tauri::command]
-pub async fn get_config(state: State<'_, SharedState>) -> Result<IsomerConfig, String> {
+pub async fn get_config(state: State<'_, SharedState>) -> Result<IsomerConfig> {
     let state = state.read().await;
     Ok(state.config.clone())
 }
 
 /// Update configuration
 #[tauri::command]
-pub async fn update_config(
-    config: IsomerConfig,
-    state: State<'_, SharedState>,
-) -> Result<(), String> {
+pub async fn update_config(config: IsomerConfig, state: State<'_, SharedState>) -> Result<()> {
     let mut state = state.write().await;
+    let mut config = config;
+    config.ports.validate()?;
+    BinaryManager::from_config(&config.binaries).validate_overrides(&state.config.binaries)?;
+    // Clearing the mnemonic rotates to a freshly generated one
+    accounts::ensure_mnemonic(&mut config)?;
     config
         .save()
-        .map_err(|e| format!("Failed to save config: {}", e))?;
+        .map_err(|e| IsomerError::io("Failed to save config", e))?;
+    state.accounts = accounts::accounts_for_config(&config);
+    state.process_manager.update_config(config.clone());
     state.config = config;
     Ok(())
 }
 
+/// List all profiles
+#[tauri::command]
+pub async fn list_profiles() -> Result<Vec<ProfileInfo>> {
+    Ok(profiles::list().iter().map(Profile::info).collect())
+}
+
+/// Create a new profile with default settings
+#[tauri::command]
+pub async fn create_profile(name: String) -> Result<ProfileInfo> {
+    Ok(Profile::create(&name)?.info())
+}
+
+/// Copy a profile's config and chain data into a new profile, as a
+/// background job. Emits `profile-cloned` once the copy is complete.
+#[tauri::command]
+pub async fn clone_profile(
+    source: String,
+    name: String,
+    state: State<'_, SharedState>,
+) -> Result<Job> {
+    let source = Profile::named(&source)?;
+    if !source.exists() {
+        return Err(IsomerError::not_found("Profile", source.name));
+    }
+    if Profile::named(&name)?.exists() {
+        return Err(IsomerError::already_exists("Profile", name));
+    }
+
+    let shared = state.inner().clone();
+    spawn_job(
+        state.inner(),
+        JobKind::CloneProfile,
+        None,
+        move |manager, report| async move {
+            // Jobs run one at a time, so services cannot start mid-copy
+            if manager.profile() == &source && !manager.running_services().is_empty() {
+                return Err("Stop all services before cloning the active profile".into());
+            }
+
+            let profile =
+                run_blocking(move || source.clone_to_with_progress(&name, &*report)).await?;
+            shared.read().await.emit("profile-cloned", profile.info());
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Stop all services and switch to another profile, as a background job.
+/// Emits `profile-switched` once the new profile is active.
+#[tauri::command]
+pub async fn switch_profile(name: String, state: State<'_, SharedState>) -> Result<Job> {
+    let profile = Profile::named(&name)?;
+    if !profile.exists() {
+        return Err(IsomerError::not_found("Profile", profile.name));
+    }
+
+    let shared = state.inner().clone();
+    spawn_job(
+        state.inner(),
+        JobKind::SwitchProfile,
+        None,
+        move |manager, report| async move {
+            shared.write().await.miner.stop();
+            run_blocking(move || manager.stop_all_with_progress(&*report)).await?;
+
+            // Services are already stopped, so the lock is only held for the swap
+            let mut state = shared.write().await;
+            state.switch_profile(profile.clone())?;
+            state.emit("profile-switched", profile.info());
+            Ok(())
+        },
+    )
+    .await
+}
+
+/// Delete a profile and its data
+#[tauri::command]
+pub async fn delete_profile(name: String) -> Result<()> {
+    Profile::named(&name)?.delete()
+}
+
 /// Check health of a specific service
 #[tauri::command]
 pub async fn check_service_health(
     service: ServiceId,
     state: State<'_, SharedState>,
-) -> Result<bool, String> {
+) -> Result<bool> {
     let state = state.read().await;
-    Ok(state
-        .process_manager
-        .check_health(service, &state.config)
-        .await)
+    Ok(state.process_manager.check_health(service).await)
+}
+
+/// Report processes occupying any configured service port
+#[tauri::command]
+pub async fn check_ports(state: State<'_, SharedState>) -> Result<Vec<PortConflict>> {
+    let config = state.read().await.config.clone();
+    Ok(ProcessManager::find_port_conflicts(&config))
 }
@@ -2,8 +2,12 @@
 //!
 //! Handles user preferences and service configuration
 
+use crate::error::IsomerError;
+use crate::process_manager::ServiceId;
+use crate::profiles::Profile;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 /// Service ports configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,9 +39,45 @@ impl Default for PortConfig {
     }
 }
 
+impl PortConfig {
+    /// All configured ports with a label, for validation and display
+    pub fn labeled(&self) -> Vec<(&'static str, u16)> {
+        vec![
+            ("bitcoind_rpc", self.bitcoind_rpc),
+            ("bitcoind_p2p", self.bitcoind_p2p),
+            ("metashrew", self.metashrew),
+            ("ord", self.ord),
+            ("esplora_http", self.esplora_http),
+            ("esplora_electrum", self.esplora_electrum),
+            ("jsonrpc", self.jsonrpc),
+            ("espo_rpc", self.espo_rpc),
+            ("espo_explorer", self.espo_explorer),
+        ]
+    }
+
+    /// Ensure no two services are configured on the same port
+    pub fn validate(&self) -> Result<(), IsomerError> {
+        let labeled = self.labeled();
+        for (i, (name, port)) in labeled.iter().enumerate() {
+            if *port == 0 {
+                return Err(IsomerError::Config {
+                    reason: format!("Port for {} must not be 0", name),
+                });
+            }
+            if let Some((other, _)) = labeled[i + 1..].iter().find(|(_, p)| p == port) {
+                return Err(IsomerError::Config {
+                    reason: format!("{} and {} both use port {}", name, other, port),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Bitcoin Core configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BitcoindConfig {
+    /// Leave `rpc_user` or `rpc_password` empty to use bitcoind's `.cookie` file
     pub rpc_user: String,
     pub rpc_password: String,
     pub fallback_fee: f64,
@@ -53,12 +93,26 @@ impl Default for BitcoindConfig {
     }
 }
 
+/// When the auto-miner produces blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MiningMode {
+    /// Mine a block every `block_interval_ms`
+    Interval,
+    /// Mine a block as soon as the mempool has transactions
+    #[default]
+    OnTransaction,
+}
+
 /// Mining configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MiningConfig {
-    /// Enable auto-mining on new mempool transactions
+    /// Enable the background auto-miner when services start
     pub auto_mine: bool,
-    /// Block interval in milliseconds when auto-mining
+    #[serde(default)]
+    pub mode: MiningMode,
+    /// Block interval in milliseconds when auto-mining (mempool poll interval in
+    /// `OnTransaction` mode)
     pub block_interval_ms: u64,
     /// Number of blocks to mine on startup to fund accounts
     pub initial_blocks: u32,
@@ -68,19 +122,171 @@ impl Default for MiningConfig {
     fn default() -> Self {
         Self {
             auto_mine: true,
+            mode: MiningMode::default(),
             block_interval_ms: 1000,
             initial_blocks: 101, // Makes coinbase spendable
         }
     }
 }
 
+/// Address type used for derived dev accounts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountType {
+    /// Native SegWit (P2WPKH), m/84'/1'/0'/0/i
+    Bip84,
+    /// Taproot (P2TR), m/86'/1'/0'/0/i
+    Bip86,
+}
+
+/// Pre-funded account configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountsConfig {
+    /// Number of accounts to derive from the mnemonic
+    pub count: usize,
+    pub account_type: AccountType,
+    /// Balance each account is topped up to on startup
+    pub balance_btc: f64,
+}
+
+impl Default for AccountsConfig {
+    fn default() -> Self {
+        Self {
+            count: 10,
+            account_type: AccountType::Bip84,
+            balance_btc: 100.0,
+        }
+    }
+}
+
+/// What to do when a service process exits on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    Never,
+    /// Restart only after a non-zero exit or a signal
+    OnFailure,
+    Always,
+}
+
+/// Supervisor restart configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartConfig {
+    pub policy: RestartPolicy,
+    /// Consecutive restarts allowed before a service is marked as crash-looping
+    pub max_restarts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Per-service policy overrides
+    #[serde(default)]
+    pub overrides: HashMap<ServiceId, RestartPolicy>,
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            policy: RestartPolicy::OnFailure,
+            max_restarts: 5,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl RestartConfig {
+    pub fn policy_for(&self, service: ServiceId) -> RestartPolicy {
+        self.overrides.get(&service).copied().unwrap_or(self.policy)
+    }
+
+    /// Exponential backoff before the next restart attempt
+    pub fn backoff(&self, failures: u32) -> std::time::Duration {
+        let ms = self
+            .initial_backoff_ms
+            .saturating_mul(1u64 << failures.min(16))
+            .min(self.max_backoff_ms);
+        std::time::Duration::from_millis(ms)
+    }
+}
+
+/// Service log file rotation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogConfig {
+    /// Size at which a service's log file is rotated
+    pub max_file_size_mb: u64,
+    /// Rotated files kept per service, in addition to the active one
+    pub max_files: u32,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size_mb: 10,
+            max_files: 5,
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn max_file_size_bytes(&self) -> u64 {
+        self.max_file_size_mb.max(1) * 1024 * 1024
+    }
+}
+
+/// Binary installation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinariesConfig {
+    /// Refuse to install binaries without a checksum from the signed release
+    /// manifest or the app itself
+    pub strict_checksums: bool,
+    /// Version each service runs instead of the newest installed release
+    #[serde(default)]
+    pub versions: HashMap<ServiceId, String>,
+    /// Look for newer releases when checking binaries
+    #[serde(default)]
+    pub check_updates: bool,
+    /// Locally built binaries used instead of the installed ones
+    #[serde(default)]
+    pub paths: HashMap<ServiceId, PathBuf>,
+    /// Locally built alkanes.wasm per service, instead of the downloaded one
+    #[serde(default)]
+    pub wasm_paths: HashMap<ServiceId, PathBuf>,
+}
+
+impl Default for BinariesConfig {
+    fn default() -> Self {
+        Self {
+            strict_checksums: true,
+            versions: HashMap::new(),
+            check_updates: false,
+            paths: HashMap::new(),
+            wasm_paths: HashMap::new(),
+        }
+    }
+}
+
+impl BinariesConfig {
+    /// Whether a service runs a locally built binary or alkanes.wasm
+    pub fn is_custom(&self, service: ServiceId) -> bool {
+        self.paths.contains_key(&service) || self.wasm_paths.contains_key(&service)
+    }
+}
+
 /// Complete Isomer configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IsomerConfig {
     pub ports: PortConfig,
     pub bitcoind: BitcoindConfig,
     pub mining: MiningConfig,
-    /// Mnemonic for deterministic wallet generation (optional)
+    #[serde(default)]
+    pub accounts: AccountsConfig,
+    #[serde(default)]
+    pub restart: RestartConfig,
+    #[serde(default)]
+    pub logs: LogConfig,
+    #[serde(default)]
+    pub binaries: BinariesConfig,
+    /// Mnemonic for deterministic wallet generation (generated on first launch)
     pub mnemonic: Option<String>,
 }
 
@@ -90,22 +296,30 @@ impl Default for IsomerConfig {
             ports: PortConfig::default(),
             bitcoind: BitcoindConfig::default(),
             mining: MiningConfig::default(),
+            accounts: AccountsConfig::default(),
+            restart: RestartConfig::default(),
+            logs: LogConfig::default(),
+            binaries: BinariesConfig::default(),
             mnemonic: None,
         }
     }
 }
 
 impl IsomerConfig {
-    /// Get the config file path
+    /// Get the config file path of the active profile
     pub fn config_path() -> PathBuf {
-        get_data_dir().join("config.json")
+        Profile::active().config_path()
     }
 
-    /// Load config from disk, or create default if not exists
+    /// Load the active profile's config, or create default if not exists
     pub fn load() -> Self {
-        let path = Self::config_path();
+        Self::load_from(&Self::config_path())
+    }
+
+    /// Load config from a file, or create default if not exists
+    pub fn load_from(path: &Path) -> Self {
         if path.exists() {
-            match std::fs::read_to_string(&path) {
+            match std::fs::read_to_string(path) {
                 Ok(content) => match serde_json::from_str(&content) {
                     Ok(config) => return config,
                     Err(e) => tracing::warn!("Failed to parse config: {}", e),
@@ -116,9 +330,13 @@ impl IsomerConfig {
         Self::default()
     }
 
-    /// Save config to disk
+    /// Save config to the active profile
     pub fn save(&self) -> Result<(), std::io::Error> {
-        let path = Self::config_path();
+        self.save_to(&Self::config_path())
+    }
+
+    /// Save config to a file
+    pub fn save_to(&self, path: &Path) -> Result<(), std::io::Error> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
@@ -134,17 +352,7 @@ pub fn get_data_dir() -> PathBuf {
         .join("Isomer")
 }
 
-/// Get the binary directory
+/// Get the binary directory (shared by all profiles)
 pub fn get_bin_dir() -> PathBuf {
     get_data_dir().join("bin")
 }
-
-/// Get the runtime data directory (bitcoin data, indexes, etc)
-pub fn get_runtime_dir() -> PathBuf {
-    get_data_dir().join("data")
-}
-
-/// Get the logs directory
-pub fn get_logs_dir() -> PathBuf {
-    get_data_dir().join("logs")
-}
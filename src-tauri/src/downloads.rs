@@ -0,0 +1,276 @@
+//! Resumable downloads into a content-addressed cache
+//!
+//! Files are streamed to a `.part` file in the cache directory, resumed with
+//! HTTP Range requests after an interruption and retried with backoff.
+//! Finished files are stored under their sha256, so reinstalling a binary or
+//! switching back to a version that was downloaded before is served from disk.
+
+use crate::config::get_data_dir;
+use crate::error::{IsomerError, Result};
+use futures_util::StreamExt;
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// How many binaries are downloaded at the same time
+pub const MAX_PARALLEL_DOWNLOADS: usize = 3;
+
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Serializes updates to the URL index between parallel downloads
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// Why a download attempt stopped
+enum AttemptError {
+    /// Worth trying again, e.g. a dropped connection or a 5xx
+    Retry(String),
+    Fatal(IsomerError),
+}
+
+/// Where downloads are cached (shared by all profiles)
+pub fn cache_dir() -> PathBuf {
+    get_data_dir().join("cache")
+}
+
+/// Cached file with this sha256, if any
+fn blob_path(digest: &str) -> PathBuf {
+    cache_dir().join("sha256").join(digest)
+}
+
+/// Maps URLs to the sha256 of what they served, for files without a published checksum
+fn index_path() -> PathBuf {
+    cache_dir().join("urls.json")
+}
+
+fn read_index() -> HashMap<String, String> {
+    std::fs::read_to_string(index_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn remember(url: &str, digest: &str) {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let mut index = read_index();
+    index.insert(url.to_string(), digest.to_string());
+    let result = serde_json::to_string_pretty(&index)
+        .map_err(|e| e.to_string())
+        .and_then(|content| std::fs::write(index_path(), content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        tracing::warn!("Failed to update download cache index: {}", e);
+    }
+}
+
+fn create_cache_dirs() -> Result<()> {
+    for dir in ["sha256", "partial"] {
+        std::fs::create_dir_all(cache_dir().join(dir))
+            .map_err(|e| IsomerError::io("Failed to create download cache", e))?;
+    }
+    Ok(())
+}
+
+/// Partial download of a URL (or local path), kept between attempts and runs
+fn partial_path(url: &str) -> PathBuf {
+    let key = hex::encode(Sha256::digest(url.as_bytes()));
+    cache_dir()
+        .join("partial")
+        .join(format!("{}.part", &key[..16]))
+}
+
+/// Name of the file a URL points at, for messages
+pub fn file_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+/// Download `url` into the cache, or find it there, and return the cached file.
+///
+/// `expected_sha256` is verified when given; a mismatch discards the file.
+/// `progress` receives the bytes downloaded so far and the total, if known.
+pub async fn fetch(
+    url: &str,
+    expected_sha256: Option<&str>,
+    progress: impl Fn(u64, Option<u64>),
+) -> Result<PathBuf> {
+    let expected = expected_sha256.map(|s| s.to_lowercase());
+    let known = expected.clone().or_else(|| read_index().remove(url));
+    if let Some(digest) = known {
+        let cached = blob_path(&digest);
+        if cached.exists() {
+            if sha256_file(&cached).await? == digest {
+                tracing::info!("Using cached {}", file_name(url));
+                return Ok(cached);
+            }
+            tracing::warn!("Cached {} is corrupt, downloading again", file_name(url));
+            let _ = std::fs::remove_file(&cached);
+        }
+    }
+
+    create_cache_dirs()?;
+
+    let client = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(15))
+        .build()
+        .unwrap_or_default();
+    let part = partial_path(url);
+    let mut backoff = INITIAL_BACKOFF;
+    for attempt in 1..=MAX_ATTEMPTS {
+        match download_to(&client, url, &part, &progress).await {
+            Ok(()) => break,
+            Err(AttemptError::Retry(reason)) if attempt < MAX_ATTEMPTS => {
+                tracing::warn!(
+                    "Download of {} interrupted ({}), retrying in {}ms",
+                    file_name(url),
+                    reason,
+                    backoff.as_millis()
+                );
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(AttemptError::Retry(reason)) => {
+                return Err(IsomerError::download(
+                    url,
+                    format!("{} (gave up after {} attempts)", reason, MAX_ATTEMPTS),
+                ))
+            }
+            Err(AttemptError::Fatal(e)) => return Err(e),
+        }
+    }
+
+    store(&part, file_name(url), expected.as_deref(), Some(url)).await
+}
+
+/// Copy a local file into the cache, verifying it exactly like a download
+pub async fn import(path: &Path, expected_sha256: Option<&str>) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    create_cache_dirs()?;
+
+    let part = partial_path(&path.display().to_string());
+    std::fs::copy(path, &part)
+        .map_err(|e| IsomerError::io(format!("Failed to read {}", path.display()), e))?;
+    let expected = expected_sha256.map(|s| s.to_lowercase());
+    store(&part, &name, expected.as_deref(), None).await
+}
+
+/// Verify a finished `.part` file and move it to its place in the cache
+async fn store(
+    part: &Path,
+    name: &str,
+    expected: Option<&str>,
+    url: Option<&str>,
+) -> Result<PathBuf> {
+    let digest = sha256_file(part).await?;
+    if let Some(expected) = expected {
+        if digest != expected {
+            let _ = std::fs::remove_file(part);
+            return Err(IsomerError::ChecksumMismatch {
+                file: name.to_string(),
+                expected: expected.to_string(),
+                actual: digest,
+            });
+        }
+    }
+
+    let cached = blob_path(&digest);
+    std::fs::rename(part, &cached)
+        .map_err(|e| IsomerError::io("Failed to move download into the cache", e))?;
+    if let Some(url) = url {
+        remember(url, &digest);
+    }
+    Ok(cached)
+}
+
+/// Stream `url` into `part`, continuing from its current length if the server allows
+async fn download_to(
+    client: &reqwest::Client,
+    url: &str,
+    part: &Path,
+    progress: &impl Fn(u64, Option<u64>),
+) -> std::result::Result<(), AttemptError> {
+    let offset = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+
+    let mut request = client.get(url);
+    if offset > 0 {
+        tracing::info!("Resuming {} at byte {}", file_name(url), offset);
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AttemptError::Retry(e.to_string()))?;
+
+    let status = response.status();
+    if status == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+        // Nothing past the end: the previous run got the whole file
+        return Ok(());
+    }
+    if !status.is_success() {
+        let reason = format!("status {}", status);
+        let transient = status.is_server_error()
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || status == StatusCode::REQUEST_TIMEOUT;
+        return Err(if transient {
+            AttemptError::Retry(reason)
+        } else {
+            AttemptError::Fatal(IsomerError::download(url, reason))
+        });
+    }
+
+    // Servers that ignore the Range header send the whole file again
+    let resumed = offset > 0 && status == StatusCode::PARTIAL_CONTENT;
+    let mut downloaded = if resumed { offset } else { 0 };
+    let total = response.content_length().map(|len| len + downloaded);
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(part)
+        .await
+        .map_err(|e| AttemptError::Fatal(IsomerError::io("Failed to open download file", e)))?;
+
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| AttemptError::Retry(e.to_string()))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| AttemptError::Fatal(IsomerError::io("Failed to write download", e)))?;
+        downloaded += chunk.len() as u64;
+        progress(downloaded, total);
+    }
+    file.flush()
+        .await
+        .map_err(|e| AttemptError::Fatal(IsomerError::io("Failed to write download", e)))?;
+
+    if total.is_some_and(|total| downloaded < total) {
+        return Err(AttemptError::Retry("connection closed early".to_string()));
+    }
+    Ok(())
+}
+
+/// Hex sha256 of a file, computed off the async runtime
+pub async fn sha256_file(path: &Path) -> Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || sha256_file_blocking(&path))
+        .await
+        .map_err(|e| format!("Checksum task failed: {}", e))?
+}
+
+/// Hex sha256 of a file, for callers outside the async runtime
+pub fn sha256_file_blocking(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| IsomerError::io(format!("Failed to open {}", path.display()), e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| IsomerError::io(format!("Failed to read {}", path.display()), e))?;
+    Ok(hex::encode(hasher.finalize()))
+}
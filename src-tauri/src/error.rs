@@ -0,0 +1,269 @@
+//! Errors returned by Isomer operations
+//!
+//! Commands serialize errors as `{ "kind", "message", "details" }`. `kind` is
+//! stable so the UI and CLI can react to specific failures (e.g. offer to
+//! download binaries on `binary_not_found`); `message` is for display.
+
+use crate::bitcoin_rpc::RpcError;
+use crate::jobs::JobKind;
+use crate::process_manager::ServiceId;
+use serde::ser::Error as _;
+use serde::{Serialize, Serializer};
+
+pub type Result<T, E = IsomerError> = std::result::Result<T, E>;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    remote = "Self",
+    tag = "kind",
+    content = "details",
+    rename_all = "snake_case"
+)]
+pub enum IsomerError {
+    /// A service binary or alkanes.wasm is not installed
+    BinaryNotFound {
+        service: ServiceId,
+        path: String,
+    },
+    /// A port a service needs is held by a process Isomer did not start
+    PortInUse {
+        service: ServiceId,
+        port: u16,
+        pid: u32,
+        process_name: String,
+    },
+    ServiceAlreadyRunning {
+        service: ServiceId,
+    },
+    /// A service could not be spawned, crashed or never became healthy
+    StartupFailed {
+        service: ServiceId,
+        reason: String,
+    },
+    /// Another start, stop or reset is still running
+    JobInProgress {
+        id: u64,
+        kind: JobKind,
+    },
+    /// bitcoind rejected a call (`code` is the Bitcoin Core error code) or was unreachable
+    Rpc {
+        code: Option<i64>,
+        message: String,
+    },
+    /// A download could not be completed
+    Download {
+        url: String,
+        reason: String,
+    },
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+    },
+    /// Strict checksum mode refused a file that could not be verified
+    Unverified {
+        file: String,
+        reason: String,
+    },
+    /// Invalid settings, e.g. two services on the same port
+    Config {
+        reason: String,
+    },
+    /// Reading or writing files failed
+    Io {
+        context: String,
+        reason: String,
+    },
+    /// A request argument was rejected
+    InvalidInput {
+        reason: String,
+    },
+    /// A named profile, snapshot or similar does not exist
+    NotFound {
+        what: String,
+        name: String,
+    },
+    /// Creating something whose name is already taken
+    AlreadyExists {
+        what: String,
+        name: String,
+    },
+    /// An operation did not finish in time
+    Timeout {
+        operation: String,
+        after_secs: u64,
+    },
+    /// Anything without a more specific kind
+    Other {
+        reason: String,
+    },
+}
+
+impl IsomerError {
+    /// File system error with what we were doing at the time
+    pub fn io(context: impl Into<String>, error: impl std::fmt::Display) -> Self {
+        IsomerError::Io {
+            context: context.into(),
+            reason: error.to_string(),
+        }
+    }
+
+    pub fn download(url: impl Into<String>, reason: impl std::fmt::Display) -> Self {
+        IsomerError::Download {
+            url: url.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn startup(service: ServiceId, reason: impl Into<String>) -> Self {
+        IsomerError::StartupFailed {
+            service,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn invalid(reason: impl Into<String>) -> Self {
+        IsomerError::InvalidInput {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn not_found(what: impl Into<String>, name: impl Into<String>) -> Self {
+        IsomerError::NotFound {
+            what: what.into(),
+            name: name.into(),
+        }
+    }
+
+    pub fn already_exists(what: impl Into<String>, name: impl Into<String>) -> Self {
+        IsomerError::AlreadyExists {
+            what: what.into(),
+            name: name.into(),
+        }
+    }
+
+    /// `operation` completes "Timed out after Ns ...", e.g. "waiting for Metashrew"
+    pub fn timeout(operation: impl Into<String>, after: std::time::Duration) -> Self {
+        IsomerError::Timeout {
+            operation: operation.into(),
+            after_secs: after.as_secs(),
+        }
+    }
+}
+
+impl std::fmt::Display for IsomerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IsomerError::BinaryNotFound { path, .. } => {
+                write!(
+                    f,
+                    "Binary not found: {}. Please download binaries first.",
+                    path
+                )
+            }
+            IsomerError::PortInUse {
+                service,
+                port,
+                pid,
+                process_name,
+            } => write!(
+                f,
+                "Port {} for {} is in use by {} (PID {}). Stop it or change the port in settings.",
+                port,
+                service.display_name(),
+                process_name,
+                pid
+            ),
+            IsomerError::ServiceAlreadyRunning { service } => {
+                write!(f, "{} is already running", service.display_name())
+            }
+            IsomerError::StartupFailed { reason, .. } => f.write_str(reason),
+            IsomerError::JobInProgress { id, .. } => {
+                write!(f, "Job {} is still running; wait for it to finish", id)
+            }
+            IsomerError::Rpc {
+                code: Some(code),
+                message,
+            } => write!(f, "Bitcoin RPC error {}: {}", code, message),
+            IsomerError::Rpc {
+                code: None,
+                message,
+            } => f.write_str(message),
+            IsomerError::Download { url, reason } => {
+                write!(f, "Download of {} failed: {}", url, reason)
+            }
+            IsomerError::ChecksumMismatch {
+                file,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Checksum verification failed for {}: expected {}, got {}",
+                file, expected, actual
+            ),
+            IsomerError::Unverified { file, reason } => {
+                write!(f, "Refusing to install unverified {}: {}", file, reason)
+            }
+            IsomerError::Config { reason } => f.write_str(reason),
+            IsomerError::Io { context, reason } => write!(f, "{}: {}", context, reason),
+            IsomerError::InvalidInput { reason } => f.write_str(reason),
+            IsomerError::NotFound { what, name } => write!(f, "{} {} does not exist", what, name),
+            IsomerError::AlreadyExists { what, name } => {
+                write!(f, "{} {} already exists", what, name)
+            }
+            IsomerError::Timeout {
+                operation,
+                after_secs,
+            } => write!(f, "Timed out after {}s {}", after_secs, operation),
+            IsomerError::Other { reason } => f.write_str(reason),
+        }
+    }
+}
+
+impl std::error::Error for IsomerError {}
+
+impl Serialize for IsomerError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        // The derived (remote) impl writes `kind` and `details`; add the display message
+        let mut value = IsomerError::serialize(self, serde_json::value::Serializer)
+            .map_err(S::Error::custom)?;
+        if let Some(object) = value.as_object_mut() {
+            object.insert("message".to_string(), self.to_string().into());
+        }
+        value.serialize(serializer)
+    }
+}
+
+/// Errors from modules that still report plain strings
+impl From<String> for IsomerError {
+    fn from(reason: String) -> Self {
+        IsomerError::Other { reason }
+    }
+}
+
+impl From<&str> for IsomerError {
+    fn from(reason: &str) -> Self {
+        IsomerError::Other {
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl From<RpcError> for IsomerError {
+    fn from(e: RpcError) -> Self {
+        IsomerError::Rpc {
+            code: e.code(),
+            message: match e {
+                RpcError::Rpc { message, .. } => message,
+                other => other.to_string(),
+            },
+        }
+    }
+}
+
+/// For the CLI and helpers that still return `Result<_, String>`
+impl From<IsomerError> for String {
+    fn from(e: IsomerError) -> Self {
+        e.to_string()
+    }
+}
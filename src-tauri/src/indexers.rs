@@ -0,0 +1,275 @@
+//! Indexer tip queries
+//!
+//! Asks Metashrew, Esplora, Ord and Espo which block they have indexed up to,
+//! so callers can tell whether they have caught up with (or reorged onto)
+//! bitcoind's chain.
+
+use crate::config::IsomerConfig;
+use crate::error::{IsomerError, Result};
+use crate::process_manager::ServiceId;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long to wait for indexers to catch up when the caller does not say
+pub const DEFAULT_SYNC_TIMEOUT_SECS: u64 = 60;
+
+/// Services that index the chain
+pub const INDEXERS: [ServiceId; 4] = [
+    ServiceId::Metashrew,
+    ServiceId::Esplora,
+    ServiceId::Ord,
+    ServiceId::Espo,
+];
+
+/// The last block an indexer has processed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerTip {
+    pub service: String,
+    pub height: Option<u64>,
+    /// Block hash at `height`, if the indexer reports it
+    pub hash: Option<String>,
+    /// Why the tip could not be read
+    pub error: Option<String>,
+}
+
+impl IndexerTip {
+    /// Whether the indexer is at `height`, and at `hash` if it reports one
+    pub fn is_at(&self, height: u64, hash: &str) -> bool {
+        self.height == Some(height) && self.hash.as_deref().is_none_or(|h| h == hash)
+    }
+}
+
+/// Indexer progress relative to bitcoind, reported in `SystemStatus`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerStatus {
+    pub service: String,
+    pub height: Option<u64>,
+    /// Blocks behind bitcoind
+    pub lag: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl IndexerStatus {
+    pub fn new(tip: IndexerTip, chain_height: u64) -> Self {
+        Self {
+            service: tip.service,
+            height: tip.height,
+            lag: tip.height.map(|h| chain_height.saturating_sub(h)),
+            error: tip.error,
+        }
+    }
+}
+
+/// Query the tip of an indexer. Errors are reported in the returned tip.
+pub async fn get_tip(service: ServiceId, config: &IsomerConfig) -> IndexerTip {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(2))
+        .build()
+        .unwrap_or_default();
+
+    let result = match service {
+        ServiceId::Metashrew => metashrew_tip(&client, config).await,
+        ServiceId::Esplora => esplora_tip(&client, config).await,
+        ServiceId::Ord => ord_tip(&client, config).await,
+        ServiceId::Espo => espo_tip(&client, config).await,
+        _ => Err(format!("{} is not an indexer", service.display_name())),
+    };
+
+    match result {
+        Ok((height, hash)) => IndexerTip {
+            service: service.id().to_string(),
+            height: Some(height),
+            hash,
+            error: None,
+        },
+        Err(e) => IndexerTip {
+            service: service.id().to_string(),
+            height: None,
+            hash: None,
+            error: Some(e),
+        },
+    }
+}
+
+/// Query the tips of several indexers concurrently
+pub async fn get_tips(services: &[ServiceId], config: &IsomerConfig) -> Vec<IndexerTip> {
+    futures_util::future::join_all(services.iter().map(|s| get_tip(*s, config))).await
+}
+
+/// Poll indexer tips until `done` holds for all of them or `timeout` passes.
+///
+/// Returns the last tips seen and whether every indexer got there.
+pub async fn wait_until(
+    config: &IsomerConfig,
+    services: &[ServiceId],
+    timeout: Duration,
+    done: impl Fn(&IndexerTip) -> bool,
+) -> (Vec<IndexerTip>, bool) {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let tips = get_tips(services, config).await;
+        let all_done = tips.iter().all(&done);
+        if all_done || Instant::now() >= deadline {
+            return (tips, all_done);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Wait until every indexer in `services` has indexed `height`
+pub async fn wait_for_sync(
+    config: &IsomerConfig,
+    services: &[ServiceId],
+    height: u64,
+    timeout: Duration,
+) -> Result<Vec<IndexerStatus>> {
+    let started = Instant::now();
+    let (tips, synced) =
+        wait_until(config, services, timeout, |tip| tip.height >= Some(height)).await;
+
+    let statuses: Vec<IndexerStatus> = tips
+        .into_iter()
+        .map(|tip| IndexerStatus::new(tip, height))
+        .collect();
+    if synced {
+        tracing::debug!(
+            "Indexers reached height {} in {}ms",
+            height,
+            started.elapsed().as_millis()
+        );
+        return Ok(statuses);
+    }
+
+    let behind: Vec<String> = statuses
+        .iter()
+        .filter(|s| s.lag != Some(0))
+        .map(|s| match (s.height, &s.error) {
+            (Some(h), _) => format!("{} at {}", s.service, h),
+            (None, Some(e)) => format!("{} ({})", s.service, e),
+            (None, None) => s.service.clone(),
+        })
+        .collect();
+    Err(IsomerError::timeout(
+        format!("waiting for height {}: {}", height, behind.join(", ")),
+        timeout,
+    ))
+}
+
+async fn metashrew_tip(
+    client: &reqwest::Client,
+    config: &IsomerConfig,
+) -> Result<(u64, Option<String>), String> {
+    let url = format!("http://127.0.0.1:{}", config.ports.metashrew);
+
+    let height = json_rpc(client, &url, "metashrew_height", serde_json::json!([]))
+        .await
+        .and_then(|v| as_height(&v))?;
+    let hash = json_rpc(
+        client,
+        &url,
+        "metashrew_getblockhash",
+        serde_json::json!([height]),
+    )
+    .await
+    .ok()
+    .and_then(|v| v.as_str().map(|h| h.trim_start_matches("0x").to_string()));
+
+    Ok((height, hash))
+}
+
+async fn esplora_tip(
+    client: &reqwest::Client,
+    config: &IsomerConfig,
+) -> Result<(u64, Option<String>), String> {
+    let base = format!("http://127.0.0.1:{}", config.ports.esplora_http);
+    let height = get_text(client, &format!("{}/blocks/tip/height", base)).await?;
+    let hash = get_text(client, &format!("{}/blocks/tip/hash", base))
+        .await
+        .ok();
+    Ok((parse_height(&height)?, hash))
+}
+
+async fn ord_tip(
+    client: &reqwest::Client,
+    config: &IsomerConfig,
+) -> Result<(u64, Option<String>), String> {
+    let base = format!("http://127.0.0.1:{}", config.ports.ord);
+    let height = get_text(client, &format!("{}/blockheight", base)).await?;
+    let hash = get_text(client, &format!("{}/blockhash", base)).await.ok();
+    Ok((parse_height(&height)?, hash))
+}
+
+async fn espo_tip(
+    client: &reqwest::Client,
+    config: &IsomerConfig,
+) -> Result<(u64, Option<String>), String> {
+    let url = format!("http://127.0.0.1:{}", config.ports.espo_rpc);
+    let result = json_rpc(client, &url, "get_espo_height", serde_json::json!({})).await?;
+
+    // Either a bare height or `{ "height": .. }`
+    let height = result.get("height").unwrap_or(&result);
+    Ok((as_height(height)?, None))
+}
+
+async fn get_text(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    response
+        .text()
+        .await
+        .map(|text| text.trim().to_string())
+        .map_err(|e| format!("Failed to read response: {}", e))
+}
+
+async fn json_rpc(
+    client: &reqwest::Client,
+    url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let result: serde_json::Value = client
+        .post(url)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("RPC call failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if let Some(error) = result.get("error").filter(|e| !e.is_null()) {
+        return Err(format!("{} failed: {}", method, error));
+    }
+    result
+        .get("result")
+        .cloned()
+        .ok_or_else(|| format!("{} returned no result", method))
+}
+
+/// Heights come back as numbers or numeric strings depending on the service
+fn as_height(value: &serde_json::Value) -> Result<u64, String> {
+    match value {
+        serde_json::Value::Number(n) => n.as_u64().ok_or_else(|| format!("Invalid height: {}", n)),
+        serde_json::Value::String(s) => parse_height(s),
+        other => Err(format!("Invalid height: {}", other)),
+    }
+}
+
+fn parse_height(text: &str) -> Result<u64, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("Invalid height: {}", text))
+}
@@ -0,0 +1,202 @@
+//! Background jobs for long-running service operations
+//!
+//! Starting, stopping and resetting the stack can take minutes. Commands
+//! register a job, run the work in the background and return right away, so
+//! the app state stays unlocked meanwhile. Every change to a job is emitted as
+//! a `job-updated` event, and jobs can also be polled by id.
+
+use crate::error::{IsomerError, Result};
+use crate::process_manager::{EventSink, Progress, ServiceId};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// How many jobs to remember, including finished ones
+const MAX_JOBS: usize = 50;
+
+/// Progress callback handed to the work of a job
+pub type Reporter = Box<dyn Fn(Progress) + Send + Sync>;
+
+/// What a job does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    StartAll,
+    StopAll,
+    Reset,
+    StartService,
+    StopService,
+    RestartService,
+    /// Swap alkanes.wasm and rebuild the Metashrew index
+    Reindex,
+    CreateSnapshot,
+    RestoreSnapshot,
+    CloneProfile,
+    SwitchProfile,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// A background operation and how far it has got
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: u64,
+    pub kind: JobKind,
+    /// Target of single-service jobs
+    pub service: Option<ServiceId>,
+    pub state: JobState,
+    /// Latest progress report, if any
+    pub progress: Option<Progress>,
+    pub error: Option<IsomerError>,
+    /// Unix timestamps in seconds
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+#[derive(Default)]
+struct JobList {
+    next_id: u64,
+    /// Oldest first
+    jobs: VecDeque<Job>,
+}
+
+/// Registry of recent jobs, shared between the app state and running jobs
+#[derive(Clone)]
+pub struct Jobs {
+    list: Arc<Mutex<JobList>>,
+    events: Option<EventSink>,
+}
+
+impl Jobs {
+    pub fn new(events: EventSink) -> Self {
+        Self {
+            list: Arc::default(),
+            events: Some(events),
+        }
+    }
+
+    /// Register a new running job.
+    ///
+    /// Service operations must not overlap, so this fails while another job runs.
+    pub fn begin(&self, kind: JobKind, service: Option<ServiceId>) -> Result<Job> {
+        let job = {
+            let mut list = self.list.lock().unwrap();
+            Self::check_idle(&list)?;
+
+            list.next_id += 1;
+            let job = Job {
+                id: list.next_id,
+                kind,
+                service,
+                state: JobState::Running,
+                progress: None,
+                error: None,
+                started_at: now(),
+                finished_at: None,
+            };
+            list.jobs.push_back(job.clone());
+            if list.jobs.len() > MAX_JOBS {
+                list.jobs.pop_front();
+            }
+            job
+        };
+
+        tracing::info!("Job {} ({:?}) started", job.id, job.kind);
+        self.publish(&job);
+        Ok(job)
+    }
+
+    /// Fail if a job is running, for other operations that start or stop services
+    pub fn ensure_idle(&self) -> Result<()> {
+        Self::check_idle(&self.list.lock().unwrap())
+    }
+
+    fn check_idle(list: &JobList) -> Result<()> {
+        match list.jobs.iter().find(|j| j.state == JobState::Running) {
+            Some(running) => Err(IsomerError::JobInProgress {
+                id: running.id,
+                kind: running.kind,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Callback recording progress for a job
+    pub fn reporter(&self, id: u64) -> Reporter {
+        let jobs = self.clone();
+        Box::new(move |progress| jobs.update(id, |job| job.progress = Some(progress)))
+    }
+
+    /// Record the outcome of a job
+    pub fn finish(&self, id: u64, result: Result<()>) {
+        match &result {
+            Ok(()) => tracing::info!("Job {} finished", id),
+            Err(e) => tracing::error!("Job {} failed: {}", id, e),
+        }
+        self.update(id, |job| {
+            job.finished_at = Some(now());
+            match result {
+                Ok(()) => job.state = JobState::Succeeded,
+                Err(e) => {
+                    job.state = JobState::Failed;
+                    job.error = Some(e);
+                }
+            }
+        });
+    }
+
+    pub fn get(&self, id: u64) -> Option<Job> {
+        let list = self.list.lock().unwrap();
+        list.jobs.iter().find(|j| j.id == id).cloned()
+    }
+
+    /// Recent jobs, newest first
+    pub fn list(&self) -> Vec<Job> {
+        let list = self.list.lock().unwrap();
+        list.jobs.iter().rev().cloned().collect()
+    }
+
+    fn update(&self, id: u64, change: impl FnOnce(&mut Job)) {
+        let job = {
+            let mut list = self.list.lock().unwrap();
+            let Some(job) = list.jobs.iter_mut().find(|j| j.id == id) else {
+                return;
+            };
+            change(job);
+            job.clone()
+        };
+        self.publish(&job);
+    }
+
+    fn publish(&self, job: &Job) {
+        if let Some(events) = &self.events {
+            match serde_json::to_value(job) {
+                Ok(payload) => events("job-updated", payload),
+                Err(e) => tracing::error!("Failed to serialize job {}: {}", job.id, e),
+            }
+        }
+    }
+}
+
+/// Run a blocking process manager operation, such as stopping a service,
+/// off the async runtime
+pub async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("Job task failed: {}", e))?
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -1,8 +1,24 @@
-mod binary_manager;
+pub mod accounts;
+pub mod binary_manager;
+pub mod bitcoin_rpc;
+pub mod chain;
 mod commands;
-mod config;
-mod process_manager;
-mod state;
+pub mod config;
+pub mod downloads;
+pub mod error;
+pub mod indexers;
+pub mod jobs;
+pub mod log_files;
+mod log_parser;
+pub mod log_stream;
+pub mod miner;
+pub mod process_manager;
+pub mod profiles;
+pub mod reindex;
+pub mod reorg;
+pub mod snapshots;
+pub mod state;
+mod supervisor;
 
 use std::sync::Arc;
 use tauri::Manager;
@@ -37,18 +53,52 @@ pub fn run() {
             commands::get_status,
             commands::start_services,
             commands::stop_services,
+            commands::start_service,
+            commands::stop_service,
+            commands::restart_service,
             commands::reset_chain,
+            commands::get_job,
+            commands::list_jobs,
+            commands::create_snapshot,
+            commands::list_snapshots,
+            commands::restore_snapshot,
+            commands::delete_snapshot,
             commands::get_logs,
+            commands::read_log_history,
+            commands::search_logs,
+            commands::subscribe_logs,
+            commands::unsubscribe_logs,
             commands::clear_logs,
             commands::faucet,
             commands::mine_blocks,
+            commands::wait_for_sync,
+            commands::reorg,
+            commands::reindex_metashrew,
             commands::get_accounts,
             commands::check_binaries,
             commands::download_binaries,
             commands::download_wasm,
+            commands::install_binaries_from_bundle,
+            commands::list_binary_versions,
+            commands::install_binary_version,
+            commands::set_binary_version,
+            commands::set_binary_path,
+            commands::set_wasm_path,
+            commands::upgrade_binary,
+            commands::rollback_binary,
             commands::get_config,
             commands::update_config,
+            commands::list_profiles,
+            commands::create_profile,
+            commands::clone_profile,
+            commands::switch_profile,
+            commands::delete_profile,
             commands::check_service_health,
+            commands::check_ports,
+            commands::start_auto_mine,
+            commands::stop_auto_mine,
+            commands::configure_auto_mine,
+            commands::get_auto_mine_status,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
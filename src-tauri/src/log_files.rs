@@ -0,0 +1,297 @@
+//! Persistent service log files
+//!
+//! Each service's output is appended to `<logs_dir>/<service>.log`, which is
+//! rotated to `<service>.log.1`, `<service>.log.2`, ... once it reaches the
+//! configured size. Files survive restarts, so earlier sessions can be read back.
+
+use crate::config::LogConfig;
+use crate::error::{IsomerError, Result};
+use crate::log_parser;
+use crate::log_stream::LogLevel;
+use crate::process_manager::{LogEntry, ServiceId};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Format a raw output line for a service log file: `<captured_at> <out|err> <line>`
+fn format_log_line(captured_at: u64, is_stderr: bool, line: &str) -> String {
+    let stream = if is_stderr { "err" } else { "out" };
+    format!("{} {} {}", captured_at, stream, line)
+}
+
+/// Parse a line written by `format_log_line` into a structured entry
+fn parse_log_line(service: ServiceId, line: &str) -> Option<LogEntry> {
+    let mut parts = line.splitn(3, ' ');
+    let captured_at = parts.next()?.parse().ok()?;
+    let is_stderr = match parts.next()? {
+        "out" => false,
+        "err" => true,
+        _ => return None,
+    };
+    let raw = parts.next().unwrap_or("");
+    Some(log_parser::parse_entry(
+        service,
+        raw,
+        is_stderr,
+        captured_at,
+    ))
+}
+
+/// Path of the active log file, or of rotated file `index` (1 is the newest)
+fn log_path(logs_dir: &Path, service: ServiceId, index: u32) -> PathBuf {
+    let name = if index == 0 {
+        format!("{}.log", service.id())
+    } else {
+        format!("{}.log.{}", service.id(), index)
+    };
+    logs_dir.join(name)
+}
+
+/// Existing log files for a service, newest first
+fn existing_log_files(logs_dir: &Path, service: ServiceId) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut index = 0;
+    loop {
+        let path = log_path(logs_dir, service, index);
+        if !path.exists() {
+            // The active file may be missing while rotated ones remain
+            if index > 0 {
+                break;
+            }
+        } else {
+            paths.push(path);
+        }
+        index += 1;
+    }
+    paths
+}
+
+/// Append-only log file for one service with size- and count-based rotation
+pub struct RotatingLogFile {
+    logs_dir: PathBuf,
+    service: ServiceId,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: u32,
+}
+
+impl RotatingLogFile {
+    /// Open (appending to) the active log file of a service
+    pub fn open(service: ServiceId, logs_dir: &Path, settings: &LogConfig) -> Result<Self> {
+        std::fs::create_dir_all(logs_dir)
+            .map_err(|e| IsomerError::io("Failed to create logs directory", e))?;
+
+        let path = log_path(logs_dir, service, 0);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| {
+                IsomerError::io(format!("Failed to open log file {}", path.display()), e)
+            })?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(Self {
+            logs_dir: logs_dir.to_path_buf(),
+            service,
+            file,
+            size,
+            max_size: settings.max_file_size_bytes(),
+            max_files: settings.max_files,
+        })
+    }
+
+    /// Append a raw output line, rotating first if the file is full
+    pub fn write_line(&mut self, captured_at: u64, is_stderr: bool, line: &str) {
+        let line = format_log_line(captured_at, is_stderr, line);
+        let len = line.len() as u64 + 1;
+
+        if self.size > 0 && self.size + len > self.max_size {
+            if let Err(e) = self.rotate() {
+                tracing::warn!("{}", e);
+            }
+        }
+
+        if writeln!(self.file, "{}", line).is_ok() {
+            self.size += len;
+        }
+    }
+
+    /// Shift `<service>.log.N` to `.N+1`, dropping the oldest, and start a new active file
+    fn rotate(&mut self) -> Result<()> {
+        if self.max_files == 0 {
+            self.file
+                .set_len(0)
+                .map_err(|e| IsomerError::io("Failed to truncate log file", e))?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        let _ = std::fs::remove_file(log_path(&self.logs_dir, self.service, self.max_files));
+        for index in (0..self.max_files).rev() {
+            let from = log_path(&self.logs_dir, self.service, index);
+            if from.exists() {
+                std::fs::rename(&from, log_path(&self.logs_dir, self.service, index + 1)).map_err(
+                    |e| IsomerError::io(format!("Failed to rotate {}", from.display()), e),
+                )?;
+            }
+        }
+
+        let path = log_path(&self.logs_dir, self.service, 0);
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| {
+                IsomerError::io(format!("Failed to open log file {}", path.display()), e)
+            })?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Filters for `search`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogSearch {
+    /// Regular expression matched against the message and target
+    pub pattern: Option<String>,
+    /// Service ids to search (all if unset)
+    pub services: Option<Vec<String>>,
+    /// Minimum level to include
+    pub min_level: Option<LogLevel>,
+    /// Inclusive Unix timestamp (seconds) bounds
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Maximum number of (most recent) results
+    pub limit: Option<usize>,
+}
+
+/// Read log entries for a service from its active and rotated files.
+///
+/// `since` and `until` are inclusive Unix timestamps (seconds). Returns the
+/// last `limit` matching entries in chronological order.
+pub fn read_history(
+    logs_dir: &Path,
+    service: ServiceId,
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: usize,
+) -> Vec<LogEntry> {
+    read_matching(logs_dir, service, since, until, limit, |_| true)
+}
+
+/// Read history for several services merged into one chronological list
+pub fn read_history_all(
+    logs_dir: &Path,
+    services: &[ServiceId],
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: usize,
+) -> Vec<LogEntry> {
+    merge_latest(
+        services
+            .iter()
+            .map(|service| read_history(logs_dir, *service, since, until, limit)),
+        limit,
+    )
+}
+
+/// Search the persisted logs of one or more services
+pub fn search(logs_dir: &Path, query: &LogSearch) -> Result<Vec<LogEntry>> {
+    let pattern = query
+        .pattern
+        .as_deref()
+        .filter(|p| !p.is_empty())
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| IsomerError::invalid(format!("Invalid search pattern: {}", e)))?;
+
+    let services = match query.services {
+        Some(ref ids) => ids
+            .iter()
+            .map(|id| id.parse::<ServiceId>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(IsomerError::invalid)?,
+        None => ServiceId::all(),
+    };
+    let limit = query.limit.unwrap_or(1000);
+
+    let matches = |entry: &LogEntry| {
+        query.min_level.is_none_or(|level| entry.level >= level)
+            && pattern.as_ref().is_none_or(|re| {
+                re.is_match(&entry.message)
+                    || entry.target.as_deref().is_some_and(|t| re.is_match(t))
+            })
+    };
+
+    Ok(merge_latest(
+        services.iter().map(|service| {
+            read_matching(logs_dir, *service, query.since, query.until, limit, matches)
+        }),
+        limit,
+    ))
+}
+
+/// Merge per-service results chronologically, keeping the last `limit`
+fn merge_latest(results: impl Iterator<Item = Vec<LogEntry>>, limit: usize) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = results.flatten().collect();
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    let start = entries.len().saturating_sub(limit);
+    entries.split_off(start)
+}
+
+/// Walk a service's log files from newest to oldest, collecting the last
+/// `limit` entries in the time range that satisfy `matches`
+fn read_matching(
+    logs_dir: &Path,
+    service: ServiceId,
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: usize,
+    matches: impl Fn(&LogEntry) -> bool,
+) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+
+    for path in existing_log_files(logs_dir, service) {
+        // A stray invalid byte must not hide the rest of the file
+        let content = match std::fs::read(&path) {
+            Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+            Err(e) => {
+                tracing::warn!("Failed to read log file {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let parsed: Vec<LogEntry> = content
+            .lines()
+            .filter_map(|line| parse_log_line(service, line))
+            .collect();
+        let oldest = parsed.first().map(|e| e.timestamp);
+
+        let mut file_entries: Vec<LogEntry> = parsed
+            .into_iter()
+            .filter(|entry| since.is_none_or(|since| entry.timestamp >= since))
+            .filter(|entry| until.is_none_or(|until| entry.timestamp <= until))
+            .filter(|entry| matches(entry))
+            .collect();
+        file_entries.append(&mut entries);
+        entries = file_entries;
+
+        if entries.len() >= limit {
+            break;
+        }
+        // Older files only contain entries from before `since`
+        if let (Some(since), Some(oldest)) = (since, oldest) {
+            if oldest < since {
+                break;
+            }
+        }
+    }
+
+    let start = entries.len().saturating_sub(limit);
+    entries.split_off(start)
+}
@@ -0,0 +1,323 @@
+//! Per-service log line parsers
+//!
+//! Extracts severity, target and timestamp from the formats our services
+//! write: Bitcoin Core, Rust `tracing`/`env_logger`/`stderrlog` (metashrew,
+//! ord, esplora, espo) and Node.js loggers (JSON-RPC).
+
+use crate::log_stream::LogLevel;
+use crate::process_manager::{LogEntry, ServiceId};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Fields extracted from a raw output line
+#[derive(Debug, Clone, Default)]
+struct ParsedLine {
+    level: Option<LogLevel>,
+    target: Option<String>,
+    /// Unix timestamp in seconds
+    timestamp: Option<u64>,
+    /// The line with the recognised prefix removed
+    message: String,
+}
+
+/// ANSI color escapes emitted by `tracing` and friends when they think they have a TTY
+static ANSI_ESCAPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+
+/// `2024-01-01T12:00:00Z [category:level] message`
+static BITCOIND_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<ts>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?Z)\s+(?:\[(?P<cat>[\w]+)(?::(?P<level>\w+))?\]\s+)?(?P<msg>.*)$").unwrap()
+});
+
+/// tracing-subscriber: `2024-01-01T12:00:00.123456Z  INFO target::path: message`
+static TRACING_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<ts>\d{4}-\d{2}-\d{2}[T ]\S+)\s+(?P<level>TRACE|DEBUG|INFO|WARN|ERROR)\s+(?:(?P<target>[\w:\-]+(?:\{[^}]*\})?):\s+)?(?P<msg>.*)$").unwrap()
+});
+
+/// env_logger: `[2024-01-01T12:00:00Z INFO  target] message`
+static ENV_LOGGER_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\[(?:(?P<ts>\d{4}-\d{2}-\d{2}T\S+)\s+)?(?P<level>TRACE|DEBUG|INFO|WARN|ERROR)\s+(?P<target>[\w:\-]+)?\s*\]\s*(?P<msg>.*)$").unwrap()
+});
+
+/// stderrlog (electrs/esplora): `2024-01-01T12:00:00.123+00:00 - INFO - message`
+static STDERRLOG_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:(?P<ts>\d{4}-\d{2}-\d{2}T\S+)\s+-\s+)?(?P<level>TRACE|DEBUG|INFO|WARN|ERROR)\s+-\s+(?P<msg>.*)$").unwrap()
+});
+
+/// Plain-text Node loggers: `[INFO] message`, `info: message`, `ERROR message`
+static NODE_TEXT_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:(?P<ts>\d{4}-\d{2}-\d{2}T\S+)\s+)?\[?(?P<level>trace|debug|info|warn|warning|error|fatal)\]?:?\s+(?P<msg>.*)$").unwrap()
+});
+
+/// Build a log entry from a raw output line captured at `captured_at` (Unix seconds).
+///
+/// Lines without a recognisable level fall back to a guess from the stream.
+pub fn parse_entry(service: ServiceId, line: &str, is_stderr: bool, captured_at: u64) -> LogEntry {
+    let parsed = parse_line(service, line);
+    LogEntry {
+        seq: 0,
+        service: service.id().to_string(),
+        timestamp: parsed.timestamp.unwrap_or(captured_at),
+        level: parsed
+            .level
+            .unwrap_or_else(|| LogLevel::from_stream(is_stderr)),
+        target: parsed.target,
+        message: parsed.message,
+        is_stderr,
+    }
+}
+
+/// Parse a raw output line from a service
+fn parse_line(service: ServiceId, line: &str) -> ParsedLine {
+    let line = ANSI_ESCAPE.replace_all(line, "");
+    let parsed = match service {
+        ServiceId::Bitcoind => parse_bitcoind(&line),
+        ServiceId::JsonRpc => parse_node(&line),
+        ServiceId::Metashrew | ServiceId::Ord | ServiceId::Esplora | ServiceId::Espo => {
+            parse_rust(&line)
+        }
+    };
+
+    parsed.unwrap_or_else(|| ParsedLine {
+        message: line.into_owned(),
+        ..Default::default()
+    })
+}
+
+fn parse_bitcoind(line: &str) -> Option<ParsedLine> {
+    let caps = BITCOIND_LINE.captures(line)?;
+    let message = caps["msg"].to_string();
+
+    // Unconditional logs have no category; warnings and errors are tagged
+    // `[warning]`/`[error]` (Core 28+) or prefixed in the message (older)
+    let (target, level) = match (caps.name("cat"), caps.name("level")) {
+        (Some(cat), None) if cat.as_str() == "warning" => (None, Some(LogLevel::Warn)),
+        (Some(cat), None) if cat.as_str() == "error" => (None, Some(LogLevel::Error)),
+        (Some(cat), level) => (
+            Some(cat.as_str().to_string()),
+            level.and_then(|l| level_from_str(l.as_str())),
+        ),
+        (None, _) => (None, None),
+    };
+    let level = level.or_else(|| {
+        if message.starts_with("Error") || message.starts_with("ERROR") {
+            Some(LogLevel::Error)
+        } else if message.starts_with("Warning") {
+            Some(LogLevel::Warn)
+        } else {
+            Some(LogLevel::Info)
+        }
+    });
+
+    Some(ParsedLine {
+        level,
+        target,
+        timestamp: parse_timestamp(&caps["ts"]),
+        message,
+    })
+}
+
+fn parse_rust(line: &str) -> Option<ParsedLine> {
+    [&*TRACING_LINE, &*ENV_LOGGER_LINE, &*STDERRLOG_LINE]
+        .iter()
+        .find_map(|re| {
+            let caps = re.captures(line)?;
+            Some(ParsedLine {
+                level: level_from_str(&caps["level"]),
+                target: caps.name("target").map(|t| t.as_str().to_string()),
+                timestamp: caps.name("ts").and_then(|ts| parse_timestamp(ts.as_str())),
+                message: caps["msg"].to_string(),
+            })
+        })
+}
+
+fn parse_node(line: &str) -> Option<ParsedLine> {
+    if line.starts_with('{') {
+        if let Some(parsed) = parse_node_json(line) {
+            return Some(parsed);
+        }
+    }
+
+    let caps = NODE_TEXT_LINE.captures(line)?;
+    Some(ParsedLine {
+        level: level_from_str(&caps["level"]),
+        target: None,
+        timestamp: caps.name("ts").and_then(|ts| parse_timestamp(ts.as_str())),
+        message: caps["msg"].to_string(),
+    })
+}
+
+/// pino/bunyan/winston JSON lines
+fn parse_node_json(line: &str) -> Option<ParsedLine> {
+    let json: serde_json::Value = serde_json::from_str(line).ok()?;
+    let object = json.as_object()?;
+
+    let level = match object.get("level")? {
+        // pino/bunyan numeric levels
+        serde_json::Value::Number(n) => match n.as_u64()? {
+            0..=19 => LogLevel::Trace,
+            20..=29 => LogLevel::Debug,
+            30..=39 => LogLevel::Info,
+            40..=49 => LogLevel::Warn,
+            _ => LogLevel::Error,
+        },
+        serde_json::Value::String(s) => level_from_str(s)?,
+        _ => return None,
+    };
+
+    let timestamp = match object.get("time").or_else(|| object.get("timestamp")) {
+        // Epoch milliseconds
+        Some(serde_json::Value::Number(n)) => n.as_u64().map(|ms| ms / 1000),
+        Some(serde_json::Value::String(s)) => parse_timestamp(s),
+        _ => None,
+    };
+
+    let message = object
+        .get("msg")
+        .or_else(|| object.get("message"))
+        .and_then(|m| m.as_str())
+        .unwrap_or(line)
+        .to_string();
+
+    Some(ParsedLine {
+        level: Some(level),
+        target: object
+            .get("name")
+            .and_then(|n| n.as_str())
+            .map(|n| n.to_string()),
+        timestamp,
+        message,
+    })
+}
+
+fn level_from_str(level: &str) -> Option<LogLevel> {
+    match level.to_ascii_lowercase().as_str() {
+        "fatal" | "critical" => Some(LogLevel::Error),
+        level => level.parse().ok(),
+    }
+}
+
+/// Parse an RFC 3339 timestamp into Unix seconds
+fn parse_timestamp(ts: &str) -> Option<u64> {
+    // tracing can print "2024-01-01 12:00:00..." with a space separator
+    let ts = ts.replacen(' ', "T", 1);
+    chrono::DateTime::parse_from_rfc3339(&ts)
+        .ok()
+        .and_then(|dt| u64::try_from(dt.timestamp()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2025-01-15T10:23:45Z
+    const TS: u64 = 1_736_936_625;
+
+    fn parse(service: ServiceId, line: &str) -> LogEntry {
+        parse_entry(service, line, false, 0)
+    }
+
+    #[test]
+    fn bitcoind_category() {
+        let entry = parse(
+            ServiceId::Bitcoind,
+            "2025-01-15T10:23:45Z [net] Added connection peer=0",
+        );
+        assert_eq!(entry.timestamp, TS);
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.target.as_deref(), Some("net"));
+        assert_eq!(entry.message, "Added connection peer=0");
+    }
+
+    #[test]
+    fn bitcoind_warning() {
+        let entry = parse(
+            ServiceId::Bitcoind,
+            "2025-01-15T10:23:45.123456Z [warning] Disk space for blocks is low",
+        );
+        assert_eq!(entry.timestamp, TS);
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.target, None);
+        assert_eq!(entry.message, "Disk space for blocks is low");
+    }
+
+    #[test]
+    fn metashrew_env_logger() {
+        let entry = parse(
+            ServiceId::Metashrew,
+            "[2025-01-15T10:23:45Z INFO  rockshrew_mono] Processing block 120",
+        );
+        assert_eq!(entry.timestamp, TS);
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.target.as_deref(), Some("rockshrew_mono"));
+        assert_eq!(entry.message, "Processing block 120");
+    }
+
+    #[test]
+    fn ord_env_logger() {
+        let entry = parse(
+            ServiceId::Ord,
+            "[2025-01-15T10:23:45Z WARN  ord::index::updater] Reorg detected at height 118",
+        );
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.target.as_deref(), Some("ord::index::updater"));
+        assert_eq!(entry.message, "Reorg detected at height 118");
+    }
+
+    #[test]
+    fn esplora_stderrlog() {
+        let entry = parse(
+            ServiceId::Esplora,
+            "2025-01-15T10:23:45.000+00:00 - ERROR - failed to connect to daemon",
+        );
+        assert_eq!(entry.timestamp, TS);
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.target, None);
+        assert_eq!(entry.message, "failed to connect to daemon");
+    }
+
+    #[test]
+    fn espo_tracing_with_colors() {
+        let entry = parse(
+            ServiceId::Espo,
+            "\x1b[2m2025-01-15T10:23:45.123456Z\x1b[0m \x1b[32m INFO\x1b[0m \x1b[2mespo::indexer\x1b[0m\x1b[2m:\x1b[0m indexed block height=120",
+        );
+        assert_eq!(entry.timestamp, TS);
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.target.as_deref(), Some("espo::indexer"));
+        assert_eq!(entry.message, "indexed block height=120");
+    }
+
+    #[test]
+    fn jsonrpc_pino() {
+        let entry = parse(
+            ServiceId::JsonRpc,
+            r#"{"level":40,"time":1736936625123,"name":"alkanes-jsonrpc","msg":"metashrew is behind"}"#,
+        );
+        assert_eq!(entry.timestamp, TS);
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.target.as_deref(), Some("alkanes-jsonrpc"));
+        assert_eq!(entry.message, "metashrew is behind");
+    }
+
+    #[test]
+    fn jsonrpc_text() {
+        let entry = parse(ServiceId::JsonRpc, "[info] listening on port 18888");
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.message, "listening on port 18888");
+    }
+
+    #[test]
+    fn unparseable_line_falls_back_to_stream() {
+        let line = "thread 'main' panicked at src/main.rs:10:5";
+        let entry = parse_entry(ServiceId::Metashrew, line, true, 42);
+        assert_eq!(entry.timestamp, 42);
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.target, None);
+        assert_eq!(entry.message, line);
+
+        let entry = parse_entry(ServiceId::Bitcoind, "Bitcoin Core starting", false, 42);
+        assert_eq!(entry.level, LogLevel::Info);
+        assert_eq!(entry.message, "Bitcoin Core starting");
+    }
+}
@@ -0,0 +1,252 @@
+//! In-memory log buffer and push-based log subscriptions
+//!
+//! Every captured line gets a sequence number. Clients subscribe with an
+//! optional filter and the last sequence number they saw, receive the
+//! buffered backlog after it, and are then sent new entries as batched
+//! `log-entry` events.
+
+use crate::process_manager::{EventSink, LogEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+/// How often pending entries are flushed to subscribers
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Severity of a log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    /// Best guess for lines without a recognisable level
+    pub fn from_stream(is_stderr: bool) -> Self {
+        if is_stderr {
+            LogLevel::Warn
+        } else {
+            LogLevel::Info
+        }
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(LogLevel::Trace),
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            _ => Err(format!("Unknown log level: {}", s)),
+        }
+    }
+}
+
+/// Which entries a subscription receives
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogFilter {
+    /// Service ids to include (all if unset)
+    pub services: Option<Vec<String>>,
+    /// Minimum level to include (all if unset)
+    pub min_level: Option<LogLevel>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.services
+            .as_ref()
+            .is_none_or(|services| services.contains(&entry.service))
+            && self.min_level.is_none_or(|level| entry.level >= level)
+    }
+}
+
+/// Returned when a client subscribes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogSubscription {
+    pub id: u64,
+    /// Buffered entries after the requested cursor that match the filter
+    pub backlog: Vec<LogEntry>,
+    /// Sequence number the subscription is caught up to
+    pub cursor: u64,
+    /// True if entries after the requested cursor were already evicted from the buffer
+    pub gap: bool,
+}
+
+/// Payload of the `log-entry` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogBatch {
+    pub subscription: u64,
+    pub entries: Vec<LogEntry>,
+    /// Pass this back to `subscribe_logs` to resume without gaps
+    pub cursor: u64,
+}
+
+struct Subscriber {
+    filter: LogFilter,
+    cursor: u64,
+}
+
+/// Bounded buffer of recent entries plus subscription bookkeeping
+pub struct LogStore {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    /// Sequence number of the last entry pushed
+    last_seq: u64,
+    /// Entries not yet delivered to subscribers
+    pending: Vec<LogEntry>,
+    subscribers: HashMap<u64, Subscriber>,
+    next_subscription_id: u64,
+}
+
+/// Log store shared between the process manager, reader threads and the flusher
+pub type SharedLogStore = Arc<Mutex<LogStore>>;
+
+impl LogStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            last_seq: 0,
+            pending: Vec::new(),
+            subscribers: HashMap::new(),
+            next_subscription_id: 1,
+        }
+    }
+
+    /// Assign the next sequence number and buffer an entry
+    pub fn push(&mut self, mut entry: LogEntry) {
+        self.last_seq += 1;
+        entry.seq = self.last_seq;
+
+        if !self.subscribers.is_empty() {
+            self.pending.push(entry.clone());
+        }
+
+        self.entries.push_back(entry);
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Last `limit` buffered entries, optionally for a single service
+    pub fn recent(&self, service_filter: Option<&str>, limit: usize) -> Vec<LogEntry> {
+        let filtered: Vec<&LogEntry> = self
+            .entries
+            .iter()
+            .filter(|l| service_filter.is_none_or(|s| l.service == s))
+            .collect();
+
+        let start = filtered.len().saturating_sub(limit);
+        filtered[start..].iter().map(|l| (*l).clone()).collect()
+    }
+
+    /// Drop buffered entries. Sequence numbers keep counting so cursors stay valid.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Register a subscription, returning buffered entries after `cursor`
+    /// (or the whole buffer if no cursor is given)
+    pub fn subscribe(&mut self, filter: LogFilter, cursor: Option<u64>) -> LogSubscription {
+        let after = cursor.unwrap_or(0);
+        let oldest = self.entries.front().map(|e| e.seq);
+        let gap = cursor.is_some() && oldest.is_some_and(|oldest| oldest > after + 1);
+
+        let backlog = self
+            .entries
+            .iter()
+            .filter(|e| e.seq > after && filter.matches(e))
+            .cloned()
+            .collect();
+
+        let id = self.next_subscription_id;
+        self.next_subscription_id += 1;
+        self.subscribers.insert(
+            id,
+            Subscriber {
+                filter,
+                cursor: self.last_seq,
+            },
+        );
+
+        LogSubscription {
+            id,
+            backlog,
+            cursor: self.last_seq,
+            gap,
+        }
+    }
+
+    /// Remove a subscription, returning whether it existed
+    pub fn unsubscribe(&mut self, id: u64) -> bool {
+        let removed = self.subscribers.remove(&id).is_some();
+        if self.subscribers.is_empty() {
+            self.pending.clear();
+        }
+        removed
+    }
+
+    /// Split pending entries into one batch per subscriber
+    fn take_batches(&mut self) -> Vec<LogBatch> {
+        if self.pending.is_empty() {
+            return Vec::new();
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let last_seq = self.last_seq;
+
+        self.subscribers
+            .iter_mut()
+            .filter_map(|(id, subscriber)| {
+                let entries: Vec<LogEntry> = pending
+                    .iter()
+                    .filter(|e| e.seq > subscriber.cursor && subscriber.filter.matches(e))
+                    .cloned()
+                    .collect();
+                subscriber.cursor = last_seq;
+
+                if entries.is_empty() {
+                    return None;
+                }
+                Some(LogBatch {
+                    subscription: *id,
+                    entries,
+                    cursor: last_seq,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Spawn a thread that periodically emits pending entries as `log-entry` events.
+///
+/// The thread exits once the store is dropped.
+pub fn spawn_flusher(store: Weak<Mutex<LogStore>>, events: EventSink) {
+    std::thread::Builder::new()
+        .name("log-flusher".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(FLUSH_INTERVAL);
+
+            let Some(store) = store.upgrade() else {
+                return;
+            };
+            let batches = store.lock().unwrap().take_batches();
+            drop(store);
+
+            for batch in batches {
+                match serde_json::to_value(&batch) {
+                    Ok(payload) => events("log-entry", payload),
+                    Err(e) => tracing::error!("Failed to serialize log batch: {}", e),
+                }
+            }
+        })
+        .expect("failed to spawn log flusher thread");
+}
@@ -0,0 +1,128 @@
+//! Background block production
+//!
+//! Implements `MiningConfig::auto_mine`: either mines on a fixed interval or
+//! as soon as the mempool has transactions.
+
+use crate::chain;
+use crate::config::{IsomerConfig, MiningConfig, MiningMode};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+/// Emitted to the frontend as `block-mined` for every auto-mined block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinedBlock {
+    pub height: u64,
+    pub mode: MiningMode,
+    /// Mempool transactions that triggered the block (0 in interval mode)
+    pub mempool_size: usize,
+}
+
+/// Auto-miner state reported to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoMineStatus {
+    pub running: bool,
+    pub settings: MiningConfig,
+    pub address: Option<String>,
+}
+
+/// Owns the background mining task
+#[derive(Default)]
+pub struct AutoMiner {
+    task: Option<JoinHandle<()>>,
+    address: Option<String>,
+}
+
+impl AutoMiner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.task.as_ref().is_some_and(|t| !t.is_finished())
+    }
+
+    pub fn status(&self, settings: &MiningConfig) -> AutoMineStatus {
+        AutoMineStatus {
+            running: self.is_running(),
+            settings: settings.clone(),
+            address: self.address.clone(),
+        }
+    }
+
+    /// Start (or restart with new settings) the mining task.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn start(
+        &mut self,
+        config: &IsomerConfig,
+        address: String,
+        on_block: impl Fn(MinedBlock) + Send + Sync + 'static,
+    ) {
+        self.stop();
+
+        tracing::info!(
+            "Starting auto-miner ({:?}, every {}ms) to {}",
+            config.mining.mode,
+            config.mining.block_interval_ms,
+            address
+        );
+
+        self.address = Some(address.clone());
+        self.task = Some(tokio::spawn(Self::run(config.clone(), address, on_block)));
+    }
+
+    /// Stop the mining task if running
+    pub fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+            tracing::info!("Auto-miner stopped");
+        }
+        self.address = None;
+    }
+
+    async fn run(
+        config: IsomerConfig,
+        address: String,
+        on_block: impl Fn(MinedBlock) + Send + Sync + 'static,
+    ) {
+        let mode = config.mining.mode;
+        // Guard against a zero interval spinning the RPC server
+        let interval = std::time::Duration::from_millis(config.mining.block_interval_ms.max(100));
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            ticker.tick().await;
+
+            let mempool_size = match mode {
+                MiningMode::Interval => 0,
+                MiningMode::OnTransaction => {
+                    let size = chain::get_chain_info(&config).await.mempool_size;
+                    if size == 0 {
+                        continue;
+                    }
+                    size
+                }
+            };
+
+            match chain::mine_blocks(&config, 1, &address).await {
+                Ok(height) => {
+                    tracing::debug!("Auto-mined block {}", height);
+                    on_block(MinedBlock {
+                        height,
+                        mode,
+                        mempool_size,
+                    });
+                }
+                // bitcoind may still be starting or be restarting; try again next tick
+                Err(e) => tracing::debug!("Auto-mine failed: {}", e),
+            }
+        }
+    }
+}
+
+impl Drop for AutoMiner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
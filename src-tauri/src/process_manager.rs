@@ -2,13 +2,22 @@
 //!
 //! Handles spawning, monitoring, and graceful shutdown of all child processes
 
-use crate::config::{get_bin_dir, get_logs_dir, get_runtime_dir, IsomerConfig};
+use crate::binary_manager::BinaryManager;
+use crate::bitcoin_rpc::{codes as rpc_codes, BitcoinRpc};
+use crate::config::{IsomerConfig, LogConfig, PortConfig};
+use crate::error::{IsomerError, Result};
+use crate::log_files::RotatingLogFile;
+use crate::log_parser;
+use crate::log_stream::{self, LogFilter, LogLevel, LogStore, LogSubscription, SharedLogStore};
+use crate::profiles::Profile;
 use crate::state::{ServiceInfo, ServiceStatus};
+use crate::supervisor::{SpawnContext, Supervisor};
 use reqwest;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::Path;
 use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Instant;
 
 /// Service identifiers
@@ -80,6 +89,43 @@ impl ServiceId {
         }
     }
 
+    /// Port reported for the service in the UI
+    pub fn primary_port(&self, ports: &PortConfig) -> u16 {
+        match self {
+            ServiceId::Bitcoind => ports.bitcoind_rpc,
+            ServiceId::Metashrew => ports.metashrew,
+            ServiceId::Ord => ports.ord,
+            ServiceId::Esplora => ports.esplora_http,
+            ServiceId::Espo => ports.espo_explorer,
+            ServiceId::JsonRpc => ports.jsonrpc,
+        }
+    }
+
+    /// All ports the service listens on
+    pub fn ports(&self, ports: &PortConfig) -> Vec<u16> {
+        match self {
+            ServiceId::Bitcoind => vec![ports.bitcoind_rpc, ports.bitcoind_p2p],
+            ServiceId::Metashrew => vec![ports.metashrew],
+            ServiceId::Ord => vec![ports.ord],
+            ServiceId::Esplora => vec![ports.esplora_http, ports.esplora_electrum],
+            ServiceId::Espo => vec![ports.espo_rpc, ports.espo_explorer],
+            ServiceId::JsonRpc => vec![ports.jsonrpc],
+        }
+    }
+
+    /// How long the service may take to pass its first health check
+    pub fn startup_timeout(&self) -> std::time::Duration {
+        let secs = match self {
+            ServiceId::Bitcoind => 60,
+            ServiceId::Metashrew => 120,
+            ServiceId::Ord => 120,
+            ServiceId::Esplora => 90,
+            ServiceId::Espo => 120,
+            ServiceId::JsonRpc => 30,
+        };
+        std::time::Duration::from_secs(secs)
+    }
+
     /// Get startup dependencies (services that must be running first)
     pub fn dependencies(&self) -> Vec<ServiceId> {
         match self {
@@ -101,163 +147,418 @@ impl ServiceId {
             ],
         }
     }
+
+    /// Services that depend on this one, in startup order
+    pub fn dependents(&self) -> Vec<ServiceId> {
+        ServiceId::all()
+            .into_iter()
+            .filter(|s| s.dependencies().contains(self))
+            .collect()
+    }
+}
+
+impl std::str::FromStr for ServiceId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ServiceId::all()
+            .into_iter()
+            .find(|service| service.id() == s.to_lowercase())
+            .ok_or_else(|| format!("Unknown service: {}", s))
+    }
+}
+
+/// A configured port that is already bound by another process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortConflict {
+    pub port: u16,
+    /// Service configured to use the port
+    pub service: ServiceId,
+    pub pid: u32,
+    pub process_name: String,
+    /// Whether the owner looks like a service left over from a previous Isomer run
+    pub is_isomer: bool,
 }
 
 /// A single log entry from a service
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
+    /// Position in the live log stream (0 for entries read back from log files)
+    pub seq: u64,
     pub service: String,
     pub timestamp: u64,
+    pub level: LogLevel,
+    /// Module or category the line was logged from, if the format includes one
+    pub target: Option<String>,
     pub message: String,
     pub is_stderr: bool,
 }
 
+/// Progress of a long-running operation such as starting the stack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+    pub message: String,
+}
+
+/// Receives progress updates; operations call it from whichever thread they run on
+pub type ProgressFn<'a> = &'a (dyn Fn(Progress) + Send + Sync);
+
 /// Information about a running process
 struct ProcessInfo {
-    child: Child,
-    started_at: Instant,
-    status: ServiceStatus,
+    supervisor: Supervisor,
+    /// Port the process was started with, which may differ from the live config
+    port: u16,
 }
 
 /// Shared log buffer type
-type LogBuffer = std::sync::Arc<std::sync::Mutex<Vec<LogEntry>>>;
+pub(crate) type LogBuffer = SharedLogStore;
+
+/// Callback used to publish process events (e.g. to the frontend)
+pub type EventSink = Arc<dyn Fn(&str, serde_json::Value) + Send + Sync>;
+
+/// Log file shared by the stdout and stderr reader threads of a service
+type LogFile = Arc<std::sync::Mutex<RotatingLogFile>>;
 
 /// Maximum number of log entries to keep
 const MAX_LOG_ENTRIES: usize = 1000;
 
 /// Manages all Isomer child processes
 pub struct ProcessManager {
-    processes: HashMap<ServiceId, ProcessInfo>,
+    /// Shared with handles so background jobs can start and stop services
+    processes: Arc<Mutex<HashMap<ServiceId, ProcessInfo>>>,
+    /// Profile whose data and log directories the services use
+    profile: Profile,
+    /// Live configuration; services pick up changes on their next start
+    config: IsomerConfig,
     /// Shared log buffer captured from all services
     log_buffer: LogBuffer,
+    events: Option<EventSink>,
+    /// Only the original manager stops the services when dropped
+    is_handle: bool,
 }
 
 impl ProcessManager {
-    pub fn new() -> Self {
-        // Clean up any orphaned processes from previous runs
-        Self::kill_orphans();
+    pub fn new(profile: Profile, config: &IsomerConfig) -> Self {
+        Self {
+            processes: Arc::new(Mutex::new(HashMap::new())),
+            profile,
+            config: config.clone(),
+            log_buffer: Arc::new(std::sync::Mutex::new(LogStore::new(MAX_LOG_ENTRIES))),
+            events: None,
+            is_handle: false,
+        }
+    }
 
+    /// A manager sharing this one's processes, logs and events, with a copy of
+    /// the current profile and config. Lets long operations run without
+    /// holding the app state lock; dropping it leaves the services running.
+    pub fn handle(&self) -> ProcessManager {
         Self {
-            processes: HashMap::new(),
-            log_buffer: std::sync::Arc::new(std::sync::Mutex::new(Vec::new())),
+            processes: self.processes.clone(),
+            profile: self.profile.clone(),
+            config: self.config.clone(),
+            log_buffer: self.log_buffer.clone(),
+            events: self.events.clone(),
+            is_handle: true,
         }
     }
 
-    /// Kill any existing processes that might be orphaned
-    fn kill_orphans() {
-        tracing::info!("Cleaning up orphaned processes...");
+    fn processes(&self) -> MutexGuard<'_, HashMap<ServiceId, ProcessInfo>> {
+        self.processes.lock().unwrap()
+    }
 
-        // 1. Kill by name
-        let binaries = vec![
-            "bitcoind",
-            "rockshrew-mono",
-            "ord",
-            "flextrs",
-            "espo",
-            "jsonrpc",
-        ];
+    /// Set the callback that receives supervisor events such as restarts and
+    /// crash loops, and batched `log-entry` events for log subscriptions
+    pub fn set_event_sink(&mut self, events: EventSink) {
+        if self.events.is_none() {
+            log_stream::spawn_flusher(Arc::downgrade(&self.log_buffer), events.clone());
+        }
+        self.events = Some(events);
+    }
 
-        #[cfg(unix)]
-        {
-            for binary in &binaries {
-                // Remove -x to be more aggressive, match substring if needed, but risky.
-                // Keeping -x but ensuring it works, maybe use "pgrep" first to log.
-                let _ = Command::new("pkill").arg("-x").arg(binary).output();
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    pub fn config(&self) -> &IsomerConfig {
+        &self.config
+    }
+
+    /// Stop all services and re-root the manager at another profile
+    pub fn switch_profile(&mut self, profile: Profile, config: IsomerConfig) -> Result<()> {
+        self.stop_all()?;
+        self.profile = profile;
+        self.config = config;
+        self.log_buffer.lock().unwrap().clear();
+        Ok(())
+    }
+
+    /// Apply an updated configuration
+    pub fn update_config(&mut self, config: IsomerConfig) {
+        for (service, info) in self.processes().iter() {
+            let port = service.primary_port(&config.ports);
+            if port != info.port {
+                tracing::info!(
+                    "{} port changed to {}, restart the service to apply",
+                    service.display_name(),
+                    port
+                );
             }
         }
+        self.config = config;
+    }
 
-        #[cfg(windows)]
-        {
-            for binary in &binaries {
-                let _ = Command::new("taskkill")
-                    .arg("/F")
-                    .arg("/IM")
-                    .arg(format!("{}.exe", binary))
-                    .output();
+    /// Kill processes left over from an earlier run: only those holding one of
+    /// the configured ports that look like Isomer services
+    pub fn kill_orphans(config: &IsomerConfig) {
+        tracing::info!("Cleaning up orphaned processes...");
+
+        // Only processes holding our ports that look like our own services;
+        // anything else with the same binary name is left alone
+        for conflict in Self::find_port_conflicts(config) {
+            if conflict.is_isomer {
+                tracing::warn!(
+                    "Port {} occupied by orphaned {} (PID {}). Force killing...",
+                    conflict.port,
+                    conflict.process_name,
+                    conflict.pid
+                );
+                Self::force_kill(conflict.pid);
+            } else {
+                tracing::warn!(
+                    "Port {} ({}) is in use by {} (PID {}); leaving it alone",
+                    conflict.port,
+                    conflict.service.display_name(),
+                    conflict.process_name,
+                    conflict.pid
+                );
             }
         }
 
-        // 2. Kill by port (The nuclear option for rogue processes)
-        // Default ports to check (hardcoded here for safety, though ideally from config)
-        let ports = vec![
-            18443, // bitcoind rpc
-            18444, // bitcoind p2p
-            8080,  // metashrew
-            8090,  // ord
-            50010, // esplora http
-            8083,  // espo rpc
-            8081,  // espo explorer / UI
-            18888, // jsonrpc
-        ];
+        // Give OS a moment to reclaim resources
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
 
-        #[cfg(unix)]
-        for port in ports {
-            // lsof -t -i:PORT returns just the PID
-            if let Ok(output) = Command::new("lsof")
-                .arg("-t")
-                .arg(format!("-i:{}", port))
-                .output()
-            {
-                if let Ok(pids_str) = String::from_utf8(output.stdout) {
-                    for pid_str in pids_str.lines() {
-                        if !pid_str.trim().is_empty() {
-                            tracing::warn!(
-                                "Port {} occupied by PID {}. Force killing...",
-                                port,
-                                pid_str
-                            );
-                            let _ = Command::new("kill").arg("-9").arg(pid_str).output();
-                        }
-                    }
-                }
-            }
+    /// Kill services a previous run of this profile left holding its ports.
+    /// Services started from another profile or data directory are left alone.
+    pub fn kill_profile_services(profile: &Profile, config: &IsomerConfig) {
+        for conflict in Self::find_profile_services(profile, config) {
+            tracing::warn!(
+                "Killing {} (PID {}) left running by profile {}",
+                conflict.process_name,
+                conflict.pid,
+                profile.name
+            );
+            Self::force_kill(conflict.pid);
         }
 
         // Give OS a moment to reclaim resources
         std::thread::sleep(std::time::Duration::from_millis(100));
     }
 
-    /// Get recent logs, optionally filtered by service
-    pub fn get_logs(&self, service_filter: Option<String>, limit: usize) -> Vec<LogEntry> {
-        let logs = self.log_buffer.lock().unwrap();
-        let filtered: Vec<LogEntry> = if let Some(ref filter) = service_filter {
-            logs.iter()
-                .filter(|l| l.service == *filter)
-                .cloned()
-                .collect()
-        } else {
-            logs.clone()
+    /// Isomer services holding the configured ports that run on this profile's data
+    pub fn find_profile_services(profile: &Profile, config: &IsomerConfig) -> Vec<PortConflict> {
+        let runtime_dir = profile.runtime_dir().display().to_string();
+        Self::find_port_conflicts(config)
+            .into_iter()
+            .filter(|conflict| conflict.is_isomer)
+            .filter(|conflict| {
+                // JSON-RPC keeps no data, so its port is all that ties it to a profile
+                conflict.service == ServiceId::JsonRpc
+                    || Self::process_args(conflict.pid)
+                        .is_some_and(|args| args.contains(&runtime_dir))
+            })
+            .collect()
+    }
+
+    /// Find processes listening on any configured service port
+    pub fn find_port_conflicts(config: &IsomerConfig) -> Vec<PortConflict> {
+        ServiceId::all()
+            .into_iter()
+            .flat_map(|service| Self::find_service_port_conflicts(service, config))
+            .collect()
+    }
+
+    /// Find processes listening on the ports of a single service
+    fn find_service_port_conflicts(service: ServiceId, config: &IsomerConfig) -> Vec<PortConflict> {
+        service
+            .ports(&config.ports)
+            .into_iter()
+            .flat_map(|port| {
+                Self::port_owners(port)
+                    .into_iter()
+                    .map(move |(pid, process_name)| PortConflict {
+                        port,
+                        service,
+                        pid,
+                        is_isomer: Self::is_isomer_process(pid, &process_name),
+                        process_name,
+                    })
+            })
+            .collect()
+    }
+
+    /// Whether a process is one of the binaries Isomer runs
+    fn is_isomer_process(pid: u32, process_name: &str) -> bool {
+        // JSON-RPC runs under node, so only a node running the bundled script counts
+        if process_name == "node" {
+            return Self::process_args(pid).is_some_and(|args| args.contains("bin/jsonrpc.js"));
+        }
+        ServiceId::all()
+            .iter()
+            .any(|s| s.binary_name() == process_name)
+    }
+
+    /// Full command line of a process
+    #[cfg(unix)]
+    fn process_args(pid: u32) -> Option<String> {
+        let output = Command::new("ps")
+            .args(["-o", "args=", "-p"])
+            .arg(pid.to_string())
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    #[cfg(not(unix))]
+    fn process_args(_pid: u32) -> Option<String> {
+        None
+    }
+
+    /// List (pid, process name) of processes listening on a TCP port
+    #[cfg(unix)]
+    fn port_owners(port: u16) -> Vec<(u32, String)> {
+        // -F pc prints "p<pid>" and "c<command>" lines; +c 0 disables name truncation
+        let output = match Command::new("lsof")
+            .args(["+c", "0", "-nP", "-sTCP:LISTEN", "-Fpc"])
+            .arg(format!("-iTCP:{}", port))
+            .output()
+        {
+            Ok(output) => output,
+            Err(_) => return Vec::new(),
         };
 
-        // Return last N entries
-        let start = filtered.len().saturating_sub(limit);
-        filtered[start..].to_vec()
+        let mut owners = Vec::new();
+        let mut pid = None;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if let Some(p) = line.strip_prefix('p') {
+                pid = p.parse().ok();
+            } else if let (Some(name), Some(p)) = (line.strip_prefix('c'), pid) {
+                owners.push((p, name.to_string()));
+            }
+        }
+        owners
+    }
+
+    #[cfg(not(unix))]
+    fn port_owners(_port: u16) -> Vec<(u32, String)> {
+        Vec::new()
+    }
+
+    pub(crate) fn force_kill(pid: u32) {
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(pid as i32, libc::SIGKILL);
+        }
+
+        #[cfg(windows)]
+        {
+            let _ = Command::new("taskkill")
+                .arg("/F")
+                .arg("/PID")
+                .arg(pid.to_string())
+                .output();
+        }
+    }
+
+    /// Get recent logs, optionally filtered by service
+    pub fn get_logs(&self, service_filter: Option<String>, limit: usize) -> Vec<LogEntry> {
+        self.log_buffer
+            .lock()
+            .unwrap()
+            .recent(service_filter.as_deref(), limit)
     }
 
     /// Clear all logs
     pub fn clear_logs(&self) {
-        let mut logs = self.log_buffer.lock().unwrap();
-        logs.clear();
-    }
-
-    /// Add a log entry (called from log reader threads)
-    fn add_log_entry(buffer: &LogBuffer, entry: LogEntry) {
-        let mut logs = buffer.lock().unwrap();
-        logs.push(entry);
-        // Keep only the last MAX_LOG_ENTRIES
-        if logs.len() > MAX_LOG_ENTRIES {
-            let excess = logs.len() - MAX_LOG_ENTRIES;
-            logs.drain(0..excess);
-        }
+        self.log_buffer.lock().unwrap().clear();
     }
 
-    /// Get the binary path for a service
-    fn get_binary_path(&self, service: ServiceId) -> PathBuf {
-        get_bin_dir().join(service.binary_name())
+    /// Subscribe to live logs, starting after `cursor` if given
+    pub fn subscribe_logs(&self, filter: LogFilter, cursor: Option<u64>) -> LogSubscription {
+        self.log_buffer.lock().unwrap().subscribe(filter, cursor)
+    }
+
+    /// Stop delivering `log-entry` events for a subscription
+    pub fn unsubscribe_logs(&self, id: u64) -> bool {
+        self.log_buffer.lock().unwrap().unsubscribe(id)
+    }
+
+    /// Spawn a thread that forwards lines from a child pipe into the log buffer
+    fn spawn_log_reader<R: std::io::Read + Send + 'static>(
+        pipe: R,
+        service: ServiceId,
+        is_stderr: bool,
+        buffer: LogBuffer,
+        log_file: Option<LogFile>,
+    ) {
+        std::thread::spawn(move || {
+            use std::io::{BufRead, BufReader};
+            let mut reader = BufReader::new(pipe);
+            let mut raw = Vec::new();
+            // Keep draining the pipe past bad UTF-8 so the child never blocks on it
+            loop {
+                raw.clear();
+                match reader.read_until(b'\n', &mut raw) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {}
+                }
+                let line = String::from_utf8_lossy(&raw);
+                let line = line.trim_end_matches(['\n', '\r']);
+                // Also print to terminal for backward compatibility
+                if is_stderr {
+                    eprintln!("{}", line);
+                } else {
+                    println!("{}", line);
+                }
+
+                let captured_at = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+
+                // Files keep the raw line; it is parsed again when read back
+                if let Some(ref file) = log_file {
+                    file.lock()
+                        .unwrap()
+                        .write_line(captured_at, is_stderr, line);
+                }
+
+                let entry = log_parser::parse_entry(service, line, is_stderr, captured_at);
+
+                buffer.lock().unwrap().push(entry);
+            }
+        });
+    }
+
+    /// Open the rotating log file for a service in the logs directory
+    fn open_log_file(service: ServiceId, logs_dir: &Path, settings: &LogConfig) -> Option<LogFile> {
+        match RotatingLogFile::open(service, logs_dir, settings) {
+            Ok(file) => Some(Arc::new(std::sync::Mutex::new(file))),
+            Err(e) => {
+                tracing::warn!("{}", e);
+                None
+            }
+        }
     }
 
     /// Build the command arguments for a service
-    fn build_args(&self, service: ServiceId, config: &IsomerConfig) -> Vec<String> {
+    fn build_args(service: ServiceId, config: &IsomerConfig, runtime_dir: &Path) -> Vec<String> {
         let ports = &config.ports;
         let btc = &config.bitcoind;
 
@@ -270,20 +571,34 @@ impl ProcessManager {
                 "-rpcbind=0.0.0.0".to_string(),
                 format!("-rpcport={}", ports.bitcoind_rpc),
                 format!("-port={}", ports.bitcoind_p2p),
-                format!("-rpcuser={}", btc.rpc_user),
-                format!("-rpcpassword={}", btc.rpc_password),
                 format!("-fallbackfee={}", btc.fallback_fee),
-                format!("-datadir={}", get_runtime_dir().join("bitcoin").display()),
-            ],
+                format!("-datadir={}", runtime_dir.join("bitcoin").display()),
+            ]
+            .into_iter()
+            // Without credentials bitcoind writes a `.cookie` file instead
+            .chain(
+                (!btc.rpc_user.is_empty() && !btc.rpc_password.is_empty())
+                    .then(|| {
+                        [
+                            format!("-rpcuser={}", btc.rpc_user),
+                            format!("-rpcpassword={}", btc.rpc_password),
+                        ]
+                    })
+                    .into_iter()
+                    .flatten(),
+            )
+            .collect(),
             ServiceId::Metashrew => vec![
                 "--host".to_string(),
                 "0.0.0.0".to_string(),
                 "--port".to_string(),
                 ports.metashrew.to_string(),
                 "--indexer".to_string(),
-                get_bin_dir().join("alkanes.wasm").display().to_string(),
+                BinaryManager::alkanes_wasm_path(service, &config.binaries)
+                    .display()
+                    .to_string(),
                 "--db-path".to_string(),
-                get_runtime_dir().join("metashrew").display().to_string(),
+                runtime_dir.join("metashrew").display().to_string(),
                 "--auth".to_string(),
                 format!("{}:{}", btc.rpc_user, btc.rpc_password),
                 "--daemon-rpc-url".to_string(),
@@ -291,7 +606,7 @@ impl ProcessManager {
             ],
             ServiceId::Ord => vec![
                 "--data-dir".to_string(),
-                get_runtime_dir().join("ord").display().to_string(),
+                runtime_dir.join("ord").display().to_string(),
                 "--index-transactions".to_string(),
                 "--index-addresses".to_string(),
                 "--index-sats".to_string(),
@@ -305,7 +620,7 @@ impl ProcessManager {
                 "--bitcoin-rpc-password".to_string(),
                 btc.rpc_password.clone(),
                 "--bitcoin-data-dir".to_string(),
-                get_runtime_dir().join("bitcoin").display().to_string(),
+                runtime_dir.join("bitcoin").display().to_string(),
                 "server".to_string(),
                 "--http-port".to_string(),
                 ports.ord.to_string(),
@@ -313,9 +628,9 @@ impl ProcessManager {
             ServiceId::Esplora => vec![
                 "-vvv".to_string(),
                 "--db-dir".to_string(),
-                get_runtime_dir().join("esplora").display().to_string(),
+                runtime_dir.join("esplora").display().to_string(),
                 "--daemon-dir".to_string(),
-                get_runtime_dir().join("bitcoin").display().to_string(),
+                runtime_dir.join("bitcoin").display().to_string(),
                 "--network".to_string(),
                 "regtest".to_string(),
                 "--daemon-rpc-addr".to_string(),
@@ -327,13 +642,13 @@ impl ProcessManager {
                 "--auth".to_string(),
                 format!("{}:{}", btc.rpc_user, btc.rpc_password),
             ],
-            ServiceId::JsonRpc => vec![get_bin_dir()
-                .join("jsonrpc/bin/jsonrpc.js")
+            ServiceId::JsonRpc => vec![BinaryManager::binary_path(service, &config.binaries)
+                .join("bin/jsonrpc.js")
                 .display()
                 .to_string()],
             ServiceId::Espo => vec![
                 "--config-path".to_string(),
-                get_runtime_dir()
+                runtime_dir
                     .join("espo")
                     .join("config.json")
                     .display()
@@ -343,10 +658,10 @@ impl ProcessManager {
     }
 
     /// Prepare the configuration file for Espo
-    fn prepare_espo_config(&self, config: &IsomerConfig) -> Result<(), String> {
-        let espo_dir = get_runtime_dir().join("espo");
+    fn prepare_espo_config(config: &IsomerConfig, runtime_dir: &Path) -> Result<()> {
+        let espo_dir = runtime_dir.join("espo");
         std::fs::create_dir_all(&espo_dir)
-            .map_err(|e| format!("Failed to create espo directory: {}", e))?;
+            .map_err(|e| IsomerError::io("Failed to create espo directory", e))?;
 
         let ports = &config.ports;
         let btc = &config.bitcoind;
@@ -356,7 +671,7 @@ impl ProcessManager {
             "rpc_port": ports.espo_rpc,
             "explorer_host": format!("0.0.0.0:{}", ports.espo_explorer),
             "metashrew_rpc_url": format!("http://127.0.0.1:{}", ports.metashrew),
-            "readonly_metashrew_db_dir": get_runtime_dir().join("metashrew").display().to_string(),
+            "readonly_metashrew_db_dir": runtime_dir.join("metashrew").display().to_string(),
             "electrum_rpc_url": format!("127.0.0.1:{}", ports.esplora_electrum),
             "bitcoin_rpc_url": format!("http://127.0.0.1:{}", ports.bitcoind_rpc),
             "bitcoind_rpc_url": format!("http://127.0.0.1:{}", ports.bitcoind_rpc),
@@ -364,8 +679,8 @@ impl ProcessManager {
             "bitcoind_rpc_user": btc.rpc_user.clone(),
             "bitcoin_rpc_pass": btc.rpc_password,
             "bitcoind_rpc_pass": btc.rpc_password.clone(),
-            "bitcoin_blocks_dir": get_runtime_dir().join("bitcoin/regtest/blocks").display().to_string(),
-            "bitcoind_blocks_dir": get_runtime_dir().join("bitcoin/regtest/blocks").display().to_string(),
+            "bitcoin_blocks_dir": runtime_dir.join("bitcoin/regtest/blocks").display().to_string(),
+            "bitcoind_blocks_dir": runtime_dir.join("bitcoin/regtest/blocks").display().to_string(),
             "db_path": espo_dir.join("db").display().to_string(),
             "indexer_enabled": true,
             "mempool_enabled": true,
@@ -376,15 +691,15 @@ impl ProcessManager {
 
         let config_path = espo_dir.join("config.json");
         let content = serde_json::to_string_pretty(&espo_config)
-            .map_err(|e| format!("Failed to serialize espo config: {}", e))?;
+            .map_err(|e| IsomerError::io("Failed to serialize espo config", e))?;
         std::fs::write(config_path, content)
-            .map_err(|e| format!("Failed to write espo config: {}", e))?;
+            .map_err(|e| IsomerError::io("Failed to write espo config", e))?;
 
         Ok(())
     }
 
     /// Build environment variables for a service
-    fn build_env(&self, service: ServiceId, config: &IsomerConfig) -> HashMap<String, String> {
+    fn build_env(service: ServiceId, config: &IsomerConfig) -> HashMap<String, String> {
         let mut env = HashMap::new();
         let ports = &config.ports;
         let btc = &config.bitcoind;
@@ -425,36 +740,116 @@ impl ProcessManager {
     }
 
     /// Start a single service
-    pub fn start_service(
-        &mut self,
+    pub fn start_service(&self, service: ServiceId) -> Result<()> {
+        if self.is_running(service) {
+            return Err(IsomerError::ServiceAlreadyRunning { service });
+        }
+
+        // Report who owns a port before touching anything; only our own orphans get killed
+        for conflict in Self::find_service_port_conflicts(service, &self.config) {
+            if !conflict.is_isomer {
+                return Err(IsomerError::PortInUse {
+                    service,
+                    port: conflict.port,
+                    pid: conflict.pid,
+                    process_name: conflict.process_name,
+                });
+            }
+            tracing::warn!(
+                "Port {} occupied by orphaned {} (PID {}). Force killing...",
+                conflict.port,
+                conflict.process_name,
+                conflict.pid
+            );
+            Self::force_kill(conflict.pid);
+        }
+
+        let child = Self::spawn_child(service, &self.config, &self.profile, &self.log_buffer)?;
+        let pid = child.id();
+
+        let supervisor = Supervisor::spawn(
+            service,
+            child,
+            SpawnContext {
+                config: self.config.clone(),
+                profile: self.profile.clone(),
+                log_buffer: self.log_buffer.clone(),
+                events: self.events.clone(),
+            },
+        );
+
+        self.processes().insert(
+            service,
+            ProcessInfo {
+                supervisor,
+                port: service.primary_port(&self.config.ports),
+            },
+        );
+        tracing::info!("{} started with PID {}", service.display_name(), pid);
+        Ok(())
+    }
+
+    /// Spawn a service process with its output captured into the log buffer
+    pub(crate) fn spawn_child(
         service: ServiceId,
         config: &IsomerConfig,
-    ) -> Result<(), String> {
-        if self.processes.contains_key(&service) {
-            return Err(format!("{} is already running", service.display_name()));
-        }
+        profile: &Profile,
+        log_buffer: &LogBuffer,
+    ) -> Result<Child> {
+        Self::spawn_child_with_args(service, config, profile, log_buffer, &[])
+    }
 
-        let binary_path = self.get_binary_path(service);
+    /// Spawn Metashrew with another alkanes.wasm, exiting once it has indexed
+    /// `height`. Runs outside supervision, so it is not restarted when it exits.
+    pub(crate) fn spawn_metashrew_until(&self, wasm: &Path, height: u64) -> Result<Child> {
+        let mut config = self.config.clone();
+        config
+            .binaries
+            .wasm_paths
+            .insert(ServiceId::Metashrew, wasm.to_path_buf());
+        Self::spawn_child_with_args(
+            ServiceId::Metashrew,
+            &config,
+            &self.profile,
+            &self.log_buffer,
+            &["--exit-at".to_string(), height.to_string()],
+        )
+    }
+
+    fn spawn_child_with_args(
+        service: ServiceId,
+        config: &IsomerConfig,
+        profile: &Profile,
+        log_buffer: &LogBuffer,
+        extra_args: &[String],
+    ) -> Result<Child> {
+        let binary_path = BinaryManager::binary_path(service, &config.binaries);
         if !binary_path.exists() {
-            return Err(format!(
-                "Binary not found: {}. Please download binaries first.",
-                binary_path.display()
-            ));
+            return Err(IsomerError::BinaryNotFound {
+                service,
+                path: binary_path.display().to_string(),
+            });
+        }
+        // Custom modules are rebuilt in place, so only the managed one is checked
+        if service == ServiceId::Metashrew && !config.binaries.wasm_paths.contains_key(&service) {
+            BinaryManager::verify_alkanes_wasm()?;
         }
 
         // Ensure data directories exist
-        let _ = std::fs::create_dir_all(get_runtime_dir().join("bitcoin"));
-        let _ = std::fs::create_dir_all(get_runtime_dir().join("metashrew"));
-        let _ = std::fs::create_dir_all(get_runtime_dir().join("esplora"));
-        let _ = std::fs::create_dir_all(get_runtime_dir().join("espo"));
-        let _ = std::fs::create_dir_all(get_logs_dir());
+        let runtime_dir = profile.runtime_dir();
+        let _ = std::fs::create_dir_all(runtime_dir.join("bitcoin"));
+        let _ = std::fs::create_dir_all(runtime_dir.join("metashrew"));
+        let _ = std::fs::create_dir_all(runtime_dir.join("esplora"));
+        let _ = std::fs::create_dir_all(runtime_dir.join("espo"));
+        let _ = std::fs::create_dir_all(profile.logs_dir());
 
         if service == ServiceId::Espo {
-            self.prepare_espo_config(config)?;
+            Self::prepare_espo_config(config, &runtime_dir)?;
         }
 
-        let args = self.build_args(service, config);
-        let env = self.build_env(service, config);
+        let mut args = Self::build_args(service, config, &runtime_dir);
+        args.extend_from_slice(extra_args);
+        let env = Self::build_env(service, config);
 
         tracing::info!("Starting {} with args: {:?}", service.display_name(), args);
 
@@ -470,318 +865,305 @@ impl ProcessManager {
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
-        match cmd.spawn() {
-            Ok(mut child) => {
-                let pid = child.id();
-                let service_name = service.id().to_string();
-
-                // Capture stdout
-                if let Some(stdout) = child.stdout.take() {
-                    let buffer = self.log_buffer.clone();
-                    let name = service_name.clone();
-                    std::thread::spawn(move || {
-                        use std::io::{BufRead, BufReader};
-                        let reader = BufReader::new(stdout);
-                        for line in reader.lines() {
-                            if let Ok(line) = line {
-                                // Also print to terminal for backward compatibility
-                                println!("{}", line);
-
-                                let entry = LogEntry {
-                                    service: name.clone(),
-                                    timestamp: std::time::SystemTime::now()
-                                        .duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap_or_default()
-                                        .as_secs(),
-                                    message: line,
-                                    is_stderr: false,
-                                };
-                                Self::add_log_entry(&buffer, entry);
-                            }
-                        }
-                    });
-                }
+        let mut child = cmd.spawn().map_err(|e| {
+            IsomerError::startup(
+                service,
+                format!("Failed to start {}: {}", service.display_name(), e),
+            )
+        })?;
 
-                // Capture stderr
-                if let Some(stderr) = child.stderr.take() {
-                    let buffer = self.log_buffer.clone();
-                    let name = service_name.clone();
-                    std::thread::spawn(move || {
-                        use std::io::{BufRead, BufReader};
-                        let reader = BufReader::new(stderr);
-                        for line in reader.lines() {
-                            if let Ok(line) = line {
-                                // Also print to terminal for backward compatibility
-                                eprintln!("{}", line);
-
-                                let entry = LogEntry {
-                                    service: name.clone(),
-                                    timestamp: std::time::SystemTime::now()
-                                        .duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap_or_default()
-                                        .as_secs(),
-                                    message: line,
-                                    is_stderr: true,
-                                };
-                                Self::add_log_entry(&buffer, entry);
-                            }
-                        }
-                    });
-                }
+        let log_file = Self::open_log_file(service, &profile.logs_dir(), &config.logs);
 
-                self.processes.insert(
-                    service,
-                    ProcessInfo {
-                        child,
-                        started_at: Instant::now(),
-                        status: ServiceStatus::Starting,
-                    },
-                );
-                tracing::info!("{} started with PID {}", service.display_name(), pid);
-                Ok(())
-            }
-            Err(e) => Err(format!("Failed to start {}: {}", service.display_name(), e)),
+        // Capture stdout
+        if let Some(stdout) = child.stdout.take() {
+            Self::spawn_log_reader(stdout, service, false, log_buffer.clone(), log_file.clone());
+        }
+
+        // Capture stderr
+        if let Some(stderr) = child.stderr.take() {
+            Self::spawn_log_reader(stderr, service, true, log_buffer.clone(), log_file);
         }
+
+        Ok(child)
     }
 
     /// Stop a single service
-    pub fn stop_service(&mut self, service: ServiceId) -> Result<(), String> {
-        if let Some(mut info) = self.processes.remove(&service) {
+    pub fn stop_service(&self, service: ServiceId) -> Result<()> {
+        // Take the process out first so the lock is not held while it shuts down
+        let process = self.processes().remove(&service);
+        if let Some(info) = process {
             tracing::info!("Stopping {}", service.display_name());
+            Ok(info.supervisor.stop(service)?)
+        } else {
+            Ok(()) // Already stopped
+        }
+    }
 
-            // Try graceful shutdown first (SIGTERM on Unix)
-            #[cfg(unix)]
-            {
-                use std::os::unix::process::CommandExt;
-                let pid = info.child.id();
-                unsafe {
-                    libc::kill(pid as i32, libc::SIGTERM);
-                }
+    /// Stop a service after stopping every running service that depends on it.
+    ///
+    /// Returns the services that were actually stopped, in startup order.
+    pub fn stop_with_dependents(&self, service: ServiceId) -> Result<Vec<ServiceId>> {
+        // Dependents come after the service in startup order and stop before it
+        let mut affected = vec![service];
+        affected.extend(service.dependents());
+        affected.retain(|s| self.is_running(*s));
+
+        for s in affected.iter().rev() {
+            self.stop_service(*s)?;
+        }
 
-                // Give it a moment to shut down gracefully
-                std::thread::sleep(std::time::Duration::from_millis(500));
+        Ok(affected)
+    }
 
-                // Force kill if still running
-                unsafe {
-                    libc::kill(pid as i32, libc::SIGKILL);
-                }
-            }
+    /// Restart a service together with any running dependents
+    pub async fn restart_service(&self, service: ServiceId) -> Result<Vec<ServiceId>> {
+        self.restart_with_progress(service, &|_| {}).await
+    }
+
+    /// Like `restart_service`, reporting each service that gets started or
+    /// becomes healthy. The stop runs on a blocking thread, since shutting a
+    /// process down waits for it to exit.
+    pub async fn restart_with_progress(
+        &self,
+        service: ServiceId,
+        progress: ProgressFn<'_>,
+    ) -> Result<Vec<ServiceId>> {
+        let stopping = self.handle();
+        let mut restarted =
+            tokio::task::spawn_blocking(move || stopping.stop_with_dependents(service))
+                .await
+                .map_err(|e| format!("Stop task failed: {}", e))??;
+        if !restarted.contains(&service) {
+            restarted.insert(0, service);
+        }
+
+        self.start_with_progress(&restarted, progress).await?;
+        Ok(restarted)
+    }
+
+    /// Start all services, each as soon as its dependencies are healthy
+    pub async fn start_all(&self) -> Result<()> {
+        self.start_with_dependencies(&ServiceId::all()).await
+    }
+
+    /// Start the given services plus any dependencies that are not running yet.
+    ///
+    /// Each service is spawned once all of its dependencies pass `check_health`,
+    /// so independent services start in parallel. A service only moves from
+    /// `Starting` to `Running` once it is healthy itself.
+    pub async fn start_with_dependencies(&self, targets: &[ServiceId]) -> Result<()> {
+        self.start_with_progress(targets, &|_| {}).await
+    }
 
-            #[cfg(windows)]
-            {
-                let _ = info.child.kill();
+    /// Like `start_with_dependencies`, reporting each service that gets
+    /// started or becomes healthy
+    pub async fn start_with_progress(
+        &self,
+        targets: &[ServiceId],
+        progress: ProgressFn<'_>,
+    ) -> Result<()> {
+        use futures_util::stream::{FuturesUnordered, StreamExt};
+        use std::collections::HashSet;
+
+        // Expand the targets with their (transitive) dependencies
+        let mut wanted: Vec<ServiceId> = Vec::new();
+        let mut stack: Vec<ServiceId> = targets.to_vec();
+        while let Some(service) = stack.pop() {
+            if !wanted.contains(&service) {
+                wanted.push(service);
+                stack.extend(service.dependencies());
             }
+        }
 
-            // Wait for process to exit
-            match info.child.wait() {
-                Ok(status) => {
-                    tracing::info!("{} stopped: {:?}", service.display_name(), status);
-                    Ok(())
-                }
-                Err(e) => Err(format!(
-                    "Error waiting for {}: {}",
-                    service.display_name(),
-                    e
-                )),
+        // Forget processes whose supervisor gave up so they are started again
+        self.processes()
+            .retain(|_, info| !info.supervisor.is_finished());
+
+        let config = self.config.clone();
+        let mut pending: Vec<ServiceId> = Vec::new();
+        let mut healthy: HashSet<ServiceId> = HashSet::new();
+        let mut waiting = FuturesUnordered::new();
+
+        for service in ServiceId::all().into_iter().filter(|s| wanted.contains(s)) {
+            if self.is_running(service) {
+                // Already running; it still has to be healthy before dependents start
+                waiting.push(Self::wait_until_healthy(service, config.clone()));
+            } else {
+                pending.push(service);
             }
-        } else {
-            Ok(()) // Already stopped
         }
-    }
 
-    /// Start all services in dependency order
-    pub fn start_all(&mut self, config: &IsomerConfig) -> Result<(), String> {
-        let order = vec![
-            ServiceId::Bitcoind,
-            ServiceId::Metashrew,
-            ServiceId::Ord,
-            ServiceId::Esplora,
-            ServiceId::Espo,
-            ServiceId::JsonRpc,
-        ];
+        let mut bootstrap = None;
+        let report = |healthy: &HashSet<ServiceId>, message: String| {
+            progress(Progress {
+                completed: healthy.len(),
+                total: wanted.len(),
+                message,
+            })
+        };
 
-        for service in order {
-            self.start_service(service, config)?;
+        loop {
+            // Launch everything whose dependencies are healthy
+            let launchable: Vec<ServiceId> = pending
+                .iter()
+                .copied()
+                .filter(|s| s.dependencies().iter().all(|d| healthy.contains(d)))
+                .collect();
+            for service in launchable {
+                pending.retain(|s| *s != service);
+                report(&healthy, format!("Starting {}", service.display_name()));
+                self.start_service(service)?;
+                waiting.push(Self::wait_until_healthy(service, config.clone()));
+            }
 
-            // After bitcoind starts, wait longer and bootstrap wallet
-            if service == ServiceId::Bitcoind {
-                // Give bitcoind time to fully initialize
-                std::thread::sleep(std::time::Duration::from_secs(2));
+            if waiting.is_empty() {
+                if let Some(service) = pending.first() {
+                    return Err(IsomerError::startup(
+                        *service,
+                        format!(
+                            "Cannot start {}: dependencies did not become healthy",
+                            service.display_name()
+                        ),
+                    ));
+                }
+                break;
+            }
 
-                // Bootstrap the wallet in a separate thread to avoid tokio runtime conflicts
-                // (reqwest::blocking creates its own runtime which conflicts with Tauri's)
-                let config_clone = config.clone();
-                let handle = std::thread::spawn(move || Self::bootstrap_wallet_sync(&config_clone));
+            // Wait for the next service to become healthy, checking for crashes meanwhile
+            let next = tokio::time::timeout(std::time::Duration::from_millis(500), waiting.next());
+            match next.await {
+                Ok(Some((service, Ok(())))) => {
+                    tracing::info!("{} is healthy", service.display_name());
+                    if let Some(info) = self.processes().get(&service) {
+                        info.supervisor.set_status(ServiceStatus::Running);
+                    }
+                    healthy.insert(service);
+                    report(&healthy, format!("{} is healthy", service.display_name()));
 
-                match handle.join() {
-                    Ok(Ok(())) => tracing::info!("Wallet bootstrap completed"),
-                    Ok(Err(e)) => tracing::warn!("Wallet bootstrap warning: {}", e),
-                    Err(_) => tracing::warn!("Wallet bootstrap thread panicked"),
+                    if service == ServiceId::Bitcoind {
+                        bootstrap = Some(tokio::spawn(Self::bootstrap_wallet(config.clone())));
+                    }
+                }
+                Ok(Some((service, Err(e)))) => {
+                    tracing::error!("{}", e);
+                    if let Some(info) = self.processes().get(&service) {
+                        info.supervisor.set_status(ServiceStatus::Error(e.clone()));
+                    }
+                    return Err(IsomerError::startup(service, e));
+                }
+                Ok(None) => {}
+                Err(_) => {
+                    if let Some(e) = self.find_startup_crash() {
+                        return Err(e);
+                    }
                 }
-            } else {
-                // Wait a bit between other services for stability
-                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+        }
+
+        if let Some(bootstrap) = bootstrap {
+            report(&healthy, "Setting up the dev wallet".to_string());
+            match bootstrap.await {
+                Ok(Ok(())) => tracing::info!("Wallet bootstrap completed"),
+                Ok(Err(e)) => tracing::warn!("Wallet bootstrap warning: {}", e),
+                Err(_) => tracing::warn!("Wallet bootstrap task panicked"),
             }
         }
 
         Ok(())
     }
 
-    /// Bootstrap the dev wallet - creates wallet and mines initial blocks if needed
-    fn bootstrap_wallet_sync(config: &IsomerConfig) -> Result<(), String> {
-        let rpc_url = format!("http://127.0.0.1:{}", config.ports.bitcoind_rpc);
+    /// Poll a service's health endpoint until it responds or its startup timeout passes
+    async fn wait_until_healthy(
+        service: ServiceId,
+        config: IsomerConfig,
+    ) -> (ServiceId, Result<(), String>) {
+        let timeout = service.startup_timeout();
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if Self::probe_health(service, &config).await {
+                return (service, Ok(()));
+            }
+            if Instant::now() >= deadline {
+                return (
+                    service,
+                    Err(format!(
+                        "{} did not become healthy within {}s",
+                        service.display_name(),
+                        timeout.as_secs()
+                    )),
+                );
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+    }
 
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    /// Find a service that exited while starting up and will not be restarted
+    fn find_startup_crash(&self) -> Option<IsomerError> {
+        self.processes().iter().find_map(|(service, info)| {
+            if !info.supervisor.is_finished() {
+                return None;
+            }
+            let state = info.supervisor.state();
+            Some(IsomerError::startup(
+                *service,
+                format!(
+                    "{} exited during startup: {}",
+                    service.display_name(),
+                    state
+                        .last_error
+                        .unwrap_or_else(|| "no error reported".to_string())
+                ),
+            ))
+        })
+    }
 
-        // 1. Check if wallet exists, create if not
-        let list_wallets: serde_json::Value = client
-            .post(&rpc_url)
-            .basic_auth(
-                &config.bitcoind.rpc_user,
-                Some(&config.bitcoind.rpc_password),
-            )
-            .json(&serde_json::json!({
-                "jsonrpc": "1.0",
-                "id": "isomer",
-                "method": "listwallets",
-                "params": []
-            }))
-            .send()
-            .map_err(|e| format!("RPC call failed: {}", e))?
-            .json()
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        let wallets = list_wallets
-            .get("result")
-            .and_then(|r| r.as_array())
-            .map(|a| a.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
-            .unwrap_or_default();
+    /// Bootstrap the dev wallet - creates wallet and mines initial blocks if needed
+    async fn bootstrap_wallet(config: IsomerConfig) -> Result<(), String> {
+        let rpc = BitcoinRpc::from_config(&config).with_timeout(std::time::Duration::from_secs(30));
 
-        if !wallets.contains(&"dev") {
+        // 1. Check if wallet exists, create if not
+        let wallets = rpc.list_wallets().await?;
+        if !wallets.iter().any(|w| w == "dev") {
             tracing::info!("Creating dev wallet...");
-            let create_result: serde_json::Value = client
-                .post(&rpc_url)
-                .basic_auth(
-                    &config.bitcoind.rpc_user,
-                    Some(&config.bitcoind.rpc_password),
-                )
-                .json(&serde_json::json!({
-                    "jsonrpc": "1.0",
-                    "id": "isomer",
-                    "method": "createwallet",
-                    "params": ["dev"]
-                }))
-                .send()
-                .map_err(|e| format!("Failed to create wallet: {}", e))?
-                .json()
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-            if let Some(error) = create_result.get("error").and_then(|e| e.as_object()) {
-                let code = error.get("code").and_then(|c| c.as_i64()).unwrap_or(0);
-                // -4 means wallet already exists, which is fine
-                if code != -4 {
-                    return Err(format!("Failed to create wallet: {:?}", error));
+            match rpc.create_wallet("dev").await {
+                Ok(()) => tracing::info!("Dev wallet created"),
+                // -4: the wallet exists on disk but is not loaded yet
+                Err(e) if e.code() == Some(rpc_codes::WALLET_ERROR) => {
+                    if let Err(e) = rpc.load_wallet("dev").await {
+                        if e.code() != Some(rpc_codes::WALLET_ALREADY_LOADED) {
+                            return Err(format!("Failed to load wallet: {}", e));
+                        }
+                    }
+                    tracing::info!("Dev wallet loaded");
                 }
+                Err(e) => return Err(format!("Failed to create wallet: {}", e)),
             }
-            tracing::info!("Dev wallet created");
         } else {
             tracing::info!("Dev wallet already exists");
         }
 
-        // 2. Load wallet if not loaded
-        let _ = client
-            .post(&rpc_url)
-            .basic_auth(
-                &config.bitcoind.rpc_user,
-                Some(&config.bitcoind.rpc_password),
-            )
-            .json(&serde_json::json!({
-                "jsonrpc": "1.0",
-                "id": "isomer",
-                "method": "loadwallet",
-                "params": ["dev"]
-            }))
-            .send();
-
-        // Use wallet-specific endpoint
-        let wallet_rpc_url = format!("{}/wallet/dev", rpc_url);
-
-        // 3. Get a new address or use existing
-        let addr_result: serde_json::Value = client
-            .post(&wallet_rpc_url)
-            .basic_auth(
-                &config.bitcoind.rpc_user,
-                Some(&config.bitcoind.rpc_password),
-            )
-            .json(&serde_json::json!({
-                "jsonrpc": "1.0",
-                "id": "isomer",
-                "method": "getnewaddress",
-                "params": ["", "bech32m"]
-            }))
-            .send()
-            .map_err(|e| format!("Failed to get address: {}", e))?
-            .json()
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        let address = addr_result
-            .get("result")
-            .and_then(|r| r.as_str())
-            .ok_or("Failed to get wallet address")?;
+        // 2. Get a new address from the wallet endpoint
+        let wallet = rpc.wallet("dev");
+        let address = wallet
+            .get_new_address("bech32m")
+            .await
+            .map_err(|e| format!("Failed to get address: {}", e))?;
 
         tracing::info!("Dev wallet address: {}", address);
 
-        // 4. Check current block height
-        let height_result: serde_json::Value = client
-            .post(&rpc_url)
-            .basic_auth(
-                &config.bitcoind.rpc_user,
-                Some(&config.bitcoind.rpc_password),
-            )
-            .json(&serde_json::json!({
-                "jsonrpc": "1.0",
-                "id": "isomer",
-                "method": "getblockcount",
-                "params": []
-            }))
-            .send()
-            .map_err(|e| format!("Failed to get block height: {}", e))?
-            .json()
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-        let current_height = height_result
-            .get("result")
-            .and_then(|r| r.as_u64())
-            .unwrap_or(0);
-
-        // 5. If chain is fresh (< 101 blocks), mine initial blocks for coinbase maturity
+        // 3. If chain is fresh (< 101 blocks), mine initial blocks for coinbase maturity
+        let current_height = rpc
+            .get_block_count()
+            .await
+            .map_err(|e| format!("Failed to get block height: {}", e))?;
         if current_height < 101 {
             let blocks_to_mine = 101 - current_height as u32;
             tracing::info!("Mining {} blocks for coinbase maturity...", blocks_to_mine);
 
-            let mine_result: serde_json::Value = client
-                .post(&rpc_url)
-                .basic_auth(
-                    &config.bitcoind.rpc_user,
-                    Some(&config.bitcoind.rpc_password),
-                )
-                .json(&serde_json::json!({
-                    "jsonrpc": "1.0",
-                    "id": "isomer",
-                    "method": "generatetoaddress",
-                    "params": [blocks_to_mine, address]
-                }))
-                .send()
-                .map_err(|e| format!("Failed to mine blocks: {}", e))?
-                .json()
-                .map_err(|e| format!("Failed to parse response: {}", e))?;
-
-            if let Some(error) = mine_result.get("error").and_then(|e| e.as_object()) {
-                return Err(format!("Failed to mine blocks: {:?}", error));
-            }
+            rpc.generate_to_address(blocks_to_mine, &address)
+                .await
+                .map_err(|e| format!("Failed to mine blocks: {}", e))?;
 
             tracing::info!("Mined {} blocks to {}", blocks_to_mine, address);
         } else {
@@ -791,11 +1173,100 @@ impl ProcessManager {
             );
         }
 
+        // 4. Top up the deterministic dev accounts
+        Self::fund_accounts(&rpc, &config, &address).await
+    }
+
+    /// Top up every derived account below its target balance from the dev wallet
+    async fn fund_accounts(
+        rpc: &BitcoinRpc,
+        config: &IsomerConfig,
+        mining_address: &str,
+    ) -> Result<(), String> {
+        let accounts = crate::accounts::accounts_for_config(config);
+        if accounts.is_empty() {
+            return Ok(());
+        }
+
+        let wallet = rpc.wallet("dev");
+        let scan_result = rpc
+            .scan_tx_out_set(crate::accounts::scan_descriptors(&accounts))
+            .await?;
+        let balances = crate::accounts::balances_from_scan(&scan_result, &accounts);
+
+        let target_sats = (config.accounts.balance_btc * 1e8).round() as u64;
+        let mut top_ups = HashMap::new();
+        let mut needed_sats = 0;
+        for account in &accounts {
+            let balance = balances.get(&account.address).copied().unwrap_or(0);
+            if balance < target_sats {
+                let amount = target_sats - balance;
+                needed_sats += amount;
+                top_ups.insert(account.address.clone(), amount as f64 / 1e8);
+            }
+        }
+
+        if top_ups.is_empty() {
+            tracing::info!("All {} dev accounts are funded", accounts.len());
+            return Ok(());
+        }
+
+        // Mine until the dev wallet holds enough mature coinbase to cover the top-ups
+        let needed_btc = needed_sats as f64 / 1e8 + 1.0; // headroom for fees
+        for _ in 0..10 {
+            let balance = wallet.get_balance().await?;
+            if balance >= needed_btc {
+                break;
+            }
+
+            // Coinbase matures after 100 blocks, so each block mined unlocks the
+            // subsidy of the block 100 below it, starting 99 below the tip
+            let height = rpc.get_block_count().await?;
+            if regtest_subsidy_sats(height.saturating_sub(99)) == 0 {
+                break;
+            }
+            let mut missing = ((needed_btc - balance) * 1e8).ceil() as u64;
+            let mut blocks = 0;
+            while missing > 0 && blocks < 500 {
+                let maturing = (height + blocks + 1).saturating_sub(100);
+                missing = missing.saturating_sub(regtest_subsidy_sats(maturing));
+                blocks += 1;
+            }
+            tracing::info!("Mining {} blocks to fund dev accounts...", blocks);
+            rpc.generate_to_address(blocks as u32, mining_address)
+                .await?;
+        }
+
+        let balance = wallet.get_balance().await?;
+        if balance < needed_btc {
+            let height = rpc.get_block_count().await?;
+            return Err(format!(
+                "The dev wallet holds {:.8} BTC but the dev accounts need {:.8} BTC, and the \
+                 block subsidy at height {} is only {:.8} BTC. Lower accounts.balance_btc or \
+                 reset the chain.",
+                balance,
+                needed_btc,
+                height,
+                regtest_subsidy_sats(height + 1) as f64 / 1e8
+            ));
+        }
+
+        wallet.send_many(&top_ups).await?;
+
+        // Confirm the funding transaction
+        rpc.generate_to_address(1, mining_address).await?;
+
+        tracing::info!("Funded {} dev accounts", top_ups.len());
         Ok(())
     }
 
     /// Stop all services in reverse dependency order
-    pub fn stop_all(&mut self) -> Result<(), String> {
+    pub fn stop_all(&self) -> Result<()> {
+        self.stop_all_with_progress(&|_| {})
+    }
+
+    /// Like `stop_all`, reporting each service as it is stopped
+    pub fn stop_all_with_progress(&self, progress: ProgressFn<'_>) -> Result<()> {
         let order = vec![
             ServiceId::JsonRpc,
             ServiceId::Espo,
@@ -804,20 +1275,50 @@ impl ProcessManager {
             ServiceId::Metashrew,
             ServiceId::Bitcoind,
         ];
-
-        for service in order {
-            self.stop_service(service)?;
+        let running: Vec<ServiceId> = order.into_iter().filter(|s| self.is_running(*s)).collect();
+
+        for (i, service) in running.iter().enumerate() {
+            progress(Progress {
+                completed: i,
+                total: running.len(),
+                message: format!("Stopping {}", service.display_name()),
+            });
+            self.stop_service(*service)?;
         }
 
         Ok(())
     }
 
     /// Reset all data - stops services and clears data directories
-    pub fn reset_data(&mut self) -> Result<(), String> {
+    pub fn reset_data(&self) -> Result<()> {
+        self.reset_data_with_progress(&|_| {})
+    }
+
+    /// Like `reset_data`, reporting each step
+    pub fn reset_data_with_progress(&self, progress: ProgressFn<'_>) -> Result<()> {
         tracing::info!("Starting chain reset procedure...");
 
+        let runtime_dir = self.profile.runtime_dir();
+        let data_dirs = vec![
+            runtime_dir.join("bitcoin"),
+            runtime_dir.join("metashrew"),
+            runtime_dir.join("esplora"),
+            runtime_dir.join("espo"),
+            runtime_dir.join("ord"),
+        ];
+        // Stopping, killing orphans, then one step per data directory
+        let total = data_dirs.len() + 2;
+        let report = |completed: usize, message: String| {
+            progress(Progress {
+                completed,
+                total,
+                message,
+            })
+        };
+
         // First, stop all services
         tracing::info!("Stopping all services...");
+        report(0, "Stopping services".to_string());
         if let Err(e) = self.stop_all() {
             tracing::warn!("Error stopping services (will attempt force kill): {}", e);
         }
@@ -826,23 +1327,17 @@ impl ProcessManager {
         tracing::info!("Waiting for processes to terminate...");
         std::thread::sleep(std::time::Duration::from_secs(2));
 
-        // Use our robust kill logic to ensure files aren't locked
+        // Kill this profile's services left over from another session so files aren't locked
         tracing::info!("Force killing any orphaned processes...");
-        Self::kill_orphans();
+        report(1, "Killing orphaned processes".to_string());
+        Self::kill_profile_services(&self.profile, &self.config);
 
         // Wait again after force kill
         std::thread::sleep(std::time::Duration::from_millis(500));
 
         // Clear data directories
-        let data_dirs = vec![
-            get_runtime_dir().join("bitcoin"),
-            get_runtime_dir().join("metashrew"),
-            get_runtime_dir().join("esplora"),
-            get_runtime_dir().join("espo"),
-            get_runtime_dir().join("ord"),
-        ];
-
-        for dir in data_dirs {
+        for (i, dir) in data_dirs.into_iter().enumerate() {
+            report(i + 2, format!("Removing {}", dir.display()));
             if dir.exists() {
                 tracing::info!("Removing data directory: {}", dir.display());
                 // Retry logic for directory deletion
@@ -858,10 +1353,12 @@ impl ProcessManager {
                         std::thread::sleep(std::time::Duration::from_secs(1));
                         attempts -= 1;
                         if attempts == 0 {
-                            return Err(format!(
-                                "Failed to remove data directory {} after retries: {}",
-                                dir.display(),
-                                e
+                            return Err(IsomerError::io(
+                                format!(
+                                    "Failed to remove data directory {} after retries",
+                                    dir.display()
+                                ),
+                                e,
                             ));
                         }
                     } else {
@@ -878,11 +1375,12 @@ impl ProcessManager {
         }
 
         tracing::info!("Chain data reset complete. Services are ready to restart.");
+        report(total, "Chain data reset".to_string());
         Ok(())
     }
 
     /// Get status of all services
-    pub fn get_all_status(&mut self) -> Vec<ServiceInfo> {
+    pub fn get_all_status(&self) -> Vec<ServiceInfo> {
         ServiceId::all()
             .into_iter()
             .map(|id| self.get_service_info(id))
@@ -890,43 +1388,26 @@ impl ProcessManager {
     }
 
     /// Get info about a specific service
-    fn get_service_info(&mut self, service: ServiceId) -> ServiceInfo {
-        let (status, pid, uptime) = if let Some(info) = self.processes.get_mut(&service) {
-            // Check if process is still running
-            match info.child.try_wait() {
-                Ok(Some(exit_status)) => {
-                    // Process has exited
-                    let status = if exit_status.success() {
-                        ServiceStatus::Stopped
-                    } else {
-                        ServiceStatus::Error(format!("Exited with code: {:?}", exit_status.code()))
-                    };
-                    (status, None, None)
-                }
-                Ok(None) => {
-                    // Process is still running
-                    (
-                        ServiceStatus::Running,
-                        Some(info.child.id()),
-                        Some(info.started_at.elapsed().as_secs()),
-                    )
-                }
-                Err(e) => {
-                    // Error checking status
-                    (
-                        ServiceStatus::Error(format!("Status check failed: {}", e)),
-                        None,
-                        None,
-                    )
-                }
+    fn get_service_info(&self, service: ServiceId) -> ServiceInfo {
+        let (status, pid, uptime, restarts, error) = match self.processes().get(&service) {
+            Some(info) => {
+                let state = info.supervisor.state();
+                // Uptime only makes sense while a process is alive
+                let uptime = state.pid.map(|_| state.started_at.elapsed().as_secs());
+                (
+                    state.status,
+                    state.pid,
+                    uptime,
+                    state.restarts,
+                    state.last_error,
+                )
             }
-        } else {
-            (ServiceStatus::Stopped, None, None)
+            None => (ServiceStatus::Stopped, None, None, 0, None),
         };
 
         // Get version from BinaryManager logic (re-using checking logic for now)
         // Ideally we'd cache this or pass BinaryManager, but for now we instantiate to check
-        let version = crate::binary_manager::BinaryManager::new()
+        let version = BinaryManager::from_config(&self.config.binaries)
             .check_binary(service)
             .status
             .into_version();
@@ -936,6 +1417,7 @@ impl ProcessManager {
             ServiceStatus::Starting => "starting",
             ServiceStatus::Running => "running",
             ServiceStatus::Error(_) => "error",
+            ServiceStatus::CrashLoop(_) => "crashloop",
         }
         .to_string();
 
@@ -949,28 +1431,44 @@ impl ProcessManager {
             port,
             uptime_secs: uptime,
             version,
+            restarts,
+            error,
+            custom: self.config.binaries.is_custom(service),
         }
     }
 
     fn get_port_for_service(&self, service: ServiceId) -> u16 {
-        // Return default ports (actual config would be accessed differently)
-        match service {
-            ServiceId::Bitcoind => 18443,
-            ServiceId::Metashrew => 8080,
-            ServiceId::Ord => 8090,
-            ServiceId::Esplora => 50010,
-            ServiceId::Espo => 8081,
-            ServiceId::JsonRpc => 18888,
-        }
+        self.processes()
+            .get(&service)
+            .map(|info| info.port)
+            .unwrap_or_else(|| service.primary_port(&self.config.ports))
+    }
+
+    /// Whether a process was started for this service
+    pub fn is_running(&self, service: ServiceId) -> bool {
+        self.processes().contains_key(&service)
+    }
+
+    /// Services currently started by this manager
+    pub fn running_services(&self) -> Vec<ServiceId> {
+        ServiceId::all()
+            .into_iter()
+            .filter(|service| self.is_running(*service))
+            .collect()
     }
 
     /// Check if a service is healthy (responding to HTTP/RPC)
-    pub async fn check_health(&self, service: ServiceId, config: &IsomerConfig) -> bool {
+    pub async fn check_health(&self, service: ServiceId) -> bool {
         // First check if process is running
-        if !self.processes.contains_key(&service) {
+        if !self.is_running(service) {
             return false;
         }
 
+        Self::probe_health(service, &self.config).await
+    }
+
+    /// Probe a service's HTTP/RPC endpoint, regardless of who started it
+    pub async fn probe_health(service: ServiceId, config: &IsomerConfig) -> bool {
         let ports = &config.ports;
         let client = reqwest::Client::builder()
             .timeout(std::time::Duration::from_secs(2))
@@ -1018,9 +1516,21 @@ impl ProcessManager {
     }
 }
 
+/// Block subsidy on regtest, which halves every 150 blocks
+fn regtest_subsidy_sats(height: u64) -> u64 {
+    let halvings = height / 150;
+    if halvings >= 64 {
+        0
+    } else {
+        5_000_000_000 >> halvings
+    }
+}
+
 impl Drop for ProcessManager {
     fn drop(&mut self) {
         // Ensure all processes are stopped when Isomer exits
-        let _ = self.stop_all();
+        if !self.is_handle {
+            let _ = self.stop_all();
+        }
     }
 }
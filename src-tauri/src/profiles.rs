@@ -0,0 +1,304 @@
+//! Named chain profiles
+//!
+//! Each profile has its own config (ports, mnemonic, ...), chain data and
+//! logs, so several projects can keep separate regtest chains. The `default`
+//! profile uses the original single-profile layout at the top of the data
+//! directory; other profiles live under `profiles/<name>`. Binaries are shared.
+
+use crate::config::{get_data_dir, IsomerConfig};
+use crate::error::{IsomerError, Result};
+use crate::process_manager::{Progress, ProgressFn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile summary reported to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub active: bool,
+    pub path: String,
+    pub config: IsomerConfig,
+}
+
+/// Contents of `profiles.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileIndex {
+    active: Option<String>,
+}
+
+/// A named profile and its directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    root: PathBuf,
+}
+
+impl Profile {
+    /// Look up a profile by name (it does not have to exist yet)
+    pub fn named(name: &str) -> Result<Self> {
+        validate_name(name)?;
+        let root = if name == DEFAULT_PROFILE {
+            get_data_dir()
+        } else {
+            profiles_dir().join(name)
+        };
+        Ok(Self {
+            name: name.to_string(),
+            root,
+        })
+    }
+
+    /// The currently selected profile, falling back to `default`
+    pub fn active() -> Self {
+        let index: ProfileIndex = std::fs::read_to_string(index_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        index
+            .active
+            .and_then(|name| Self::named(&name).ok())
+            .filter(|profile| profile.exists())
+            .unwrap_or_else(Self::default_profile)
+    }
+
+    pub fn default_profile() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_string(),
+            root: get_data_dir(),
+        }
+    }
+
+    pub fn exists(&self) -> bool {
+        self.name == DEFAULT_PROFILE || self.root.is_dir()
+    }
+
+    pub fn is_active(&self) -> bool {
+        Self::active().name == self.name
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.root.join("config.json")
+    }
+
+    /// Runtime data directory (bitcoin data, indexes, etc)
+    pub fn runtime_dir(&self) -> PathBuf {
+        self.root.join("data")
+    }
+
+    pub fn logs_dir(&self) -> PathBuf {
+        self.root.join("logs")
+    }
+
+    pub fn load_config(&self) -> IsomerConfig {
+        IsomerConfig::load_from(&self.config_path())
+    }
+
+    pub fn save_config(&self, config: &IsomerConfig) -> Result<()> {
+        config.save_to(&self.config_path()).map_err(|e| {
+            IsomerError::io(
+                format!("Failed to save config for profile {}", self.name),
+                e,
+            )
+        })
+    }
+
+    pub fn info(&self) -> ProfileInfo {
+        ProfileInfo {
+            name: self.name.clone(),
+            active: self.is_active(),
+            path: self.root.display().to_string(),
+            config: self.load_config(),
+        }
+    }
+
+    /// Create a new profile with a default config
+    pub fn create(name: &str) -> Result<Self> {
+        let profile = Self::named(name)?;
+        if profile.exists() {
+            return Err(IsomerError::already_exists("Profile", name));
+        }
+
+        std::fs::create_dir_all(&profile.root)
+            .map_err(|e| IsomerError::io("Failed to create profile directory", e))?;
+        profile.save_config(&IsomerConfig::default())?;
+
+        tracing::info!("Created profile {}", name);
+        Ok(profile)
+    }
+
+    /// Copy this profile's config and chain data into a new profile.
+    ///
+    /// Services using this profile must be stopped so the data is consistent.
+    pub fn clone_to(&self, name: &str) -> Result<Self> {
+        self.clone_to_with_progress(name, &|_| {})
+    }
+
+    /// Like `clone_to`, reporting each data directory as it is copied
+    pub fn clone_to_with_progress(&self, name: &str, progress: ProgressFn<'_>) -> Result<Self> {
+        if !self.exists() {
+            return Err(IsomerError::not_found("Profile", &self.name));
+        }
+        let profile = Self::named(name)?;
+        if profile.exists() {
+            return Err(IsomerError::already_exists("Profile", name));
+        }
+
+        std::fs::create_dir_all(&profile.root)
+            .map_err(|e| IsomerError::io("Failed to create profile directory", e))?;
+
+        let result = profile.save_config(&self.load_config()).and_then(|_| {
+            let data = self.runtime_dir();
+            if !data.exists() {
+                return Ok(());
+            }
+            let target = profile.runtime_dir();
+            std::fs::create_dir_all(&target).map_err(|e| {
+                IsomerError::io(format!("Failed to create {}", target.display()), e)
+            })?;
+
+            let entries = read_entries(&data)?;
+            for (i, source) in entries.iter().enumerate() {
+                let file_name = source.file_name().unwrap_or_default();
+                progress(Progress {
+                    completed: i,
+                    total: entries.len(),
+                    message: format!("Copying {}", file_name.to_string_lossy()),
+                });
+                copy_entry(source, &target.join(file_name))?;
+            }
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            let _ = std::fs::remove_dir_all(&profile.root);
+            return Err(e);
+        }
+
+        tracing::info!("Cloned profile {} to {}", self.name, name);
+        Ok(profile)
+    }
+
+    /// Make this the active profile
+    pub fn activate(&self) -> Result<()> {
+        if !self.exists() {
+            return Err(IsomerError::not_found("Profile", &self.name));
+        }
+
+        let index = ProfileIndex {
+            active: Some(self.name.clone()),
+        };
+        let content = serde_json::to_string_pretty(&index)
+            .map_err(|e| IsomerError::io("Failed to serialize profile index", e))?;
+        std::fs::write(index_path(), content)
+            .map_err(|e| IsomerError::io("Failed to save active profile", e))?;
+
+        tracing::info!("Switched to profile {}", self.name);
+        Ok(())
+    }
+
+    /// Delete a profile and all of its data
+    pub fn delete(&self) -> Result<()> {
+        if self.name == DEFAULT_PROFILE {
+            return Err(IsomerError::invalid(
+                "The default profile cannot be deleted",
+            ));
+        }
+        if self.is_active() {
+            return Err(IsomerError::invalid(format!(
+                "Profile {} is active; switch to another profile first",
+                self.name
+            )));
+        }
+        if !self.exists() {
+            return Err(IsomerError::not_found("Profile", &self.name));
+        }
+
+        std::fs::remove_dir_all(&self.root)
+            .map_err(|e| IsomerError::io(format!("Failed to delete profile {}", self.name), e))?;
+
+        tracing::info!("Deleted profile {}", self.name);
+        Ok(())
+    }
+}
+
+/// All existing profiles, `default` first
+pub fn list() -> Vec<Profile> {
+    let mut names: Vec<String> = std::fs::read_dir(profiles_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|name| validate_name(name).is_ok() && name != DEFAULT_PROFILE)
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+
+    std::iter::once(Profile::default_profile())
+        .chain(names.iter().filter_map(|name| Profile::named(name).ok()))
+        .collect()
+}
+
+fn profiles_dir() -> PathBuf {
+    get_data_dir().join("profiles")
+}
+
+fn index_path() -> PathBuf {
+    get_data_dir().join("profiles.json")
+}
+
+/// Profile (and snapshot) names become file names, so keep them simple
+pub(crate) fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(IsomerError::invalid(format!(
+            "Invalid name '{}': use up to 64 letters, digits, '-' or '_'",
+            name
+        )))
+    }
+}
+
+/// Recursively copy a directory
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)
+        .map_err(|e| IsomerError::io(format!("Failed to create {}", to.display()), e))?;
+
+    for source in read_entries(from)? {
+        let target = to.join(source.file_name().unwrap_or_default());
+        copy_entry(&source, &target)?;
+    }
+    Ok(())
+}
+
+fn copy_entry(source: &Path, target: &Path) -> Result<()> {
+    if source.is_dir() {
+        copy_dir(source, target)
+    } else {
+        std::fs::copy(source, target)
+            .map(|_| ())
+            .map_err(|e| IsomerError::io(format!("Failed to copy {}", source.display()), e))
+    }
+}
+
+/// Paths of the entries of a directory
+fn read_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let read_error = |e| IsomerError::io(format!("Failed to read {}", dir.display()), e);
+    std::fs::read_dir(dir)
+        .map_err(read_error)?
+        .map(|entry| entry.map(|entry| entry.path()).map_err(read_error))
+        .collect()
+}
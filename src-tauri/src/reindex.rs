@@ -0,0 +1,394 @@
+//! Hot-swapping the alkanes.wasm indexer
+//!
+//! Installs a new alkanes.wasm for Metashrew and rebuilds its index, either
+//! from scratch or from a height onwards, then restarts Metashrew and the
+//! services built on it and follows the reindex until it reaches bitcoind's
+//! tip. Metashrew cannot cut its database back in place, so keeping the
+//! blocks up to a height means replaying them with the previous module
+//! (`--exit-at`) before the new one takes over. The downloaded alkanes.wasm
+//! is shared by all profiles, so a swapped module is kept in the profile's
+//! runtime directory instead and set as its override.
+
+use crate::binary_manager::BinaryManager;
+use crate::bitcoin_rpc::BitcoinRpc;
+use crate::error::{IsomerError, Result};
+use crate::indexers;
+use crate::jobs::run_blocking;
+use crate::process_manager::{ProcessManager, Progress, ProgressFn, ServiceId};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long to wait for a replay or reindex when the caller does not say
+pub const DEFAULT_REINDEX_TIMEOUT_SECS: u64 = 600;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// alkanes.wasm installed by a reindex, in the profile's runtime directory
+const SWAPPED_WASM_FILE: &str = "alkanes.wasm";
+
+/// Where the Metashrew index is kept while it is rebuilt, until the rebuild succeeds
+const KEPT_INDEX_DIR: &str = "metashrew.old";
+
+/// Which module to load and how much of the index to rebuild
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReindexRequest {
+    /// New alkanes.wasm to install; the current one is kept if unset
+    pub wasm: Option<PathBuf>,
+    /// Delete the whole Metashrew index and rebuild it from genesis
+    #[serde(default)]
+    pub wipe: bool,
+    /// Keep the index up to this height and rebuild the blocks after it
+    pub truncate_to: Option<u64>,
+    /// How long to wait for the replay and the reindex (default 600s)
+    pub timeout_secs: Option<u64>,
+}
+
+/// Swap the alkanes.wasm Metashrew loads and rebuild its index.
+///
+/// Metashrew and its dependents are stopped first and only the ones that
+/// were running are started again. A new module is installed for the
+/// profile only, as its alkanes.wasm override, and `manager`'s config is
+/// updated to match. The old index is set aside rather than deleted, so if
+/// the rebuild fails the previous module and index stay in use and the stack
+/// is put back as it was; once Metashrew restarts on the new index there is
+/// no going back. Reindex progress is reported in blocks.
+pub async fn reindex(
+    manager: &mut ProcessManager,
+    request: &ReindexRequest,
+    progress: ProgressFn<'_>,
+) -> Result<()> {
+    let service = ServiceId::Metashrew;
+    let config = manager.config().clone();
+    let timeout = Duration::from_secs(request.timeout_secs.unwrap_or(DEFAULT_REINDEX_TIMEOUT_SECS));
+
+    if request.wipe && request.truncate_to.is_some() {
+        return Err(IsomerError::invalid(
+            "Choose either wiping the index or truncating it to a height",
+        ));
+    }
+    let runtime_dir = manager.profile().runtime_dir();
+    let swapped = runtime_dir.join(SWAPPED_WASM_FILE);
+    let installed = BinaryManager::alkanes_wasm_path(service, &config.binaries);
+    if let Some(wasm) = &request.wasm {
+        if config.binaries.wasm_paths.contains_key(&service) && installed != swapped {
+            return Err(IsomerError::invalid(format!(
+                "Metashrew loads the custom alkanes.wasm {}; rebuild it in place or clear the override",
+                installed.display()
+            )));
+        }
+        if !BinaryManager::is_wasm_module(wasm) {
+            return Err(IsomerError::invalid(format!(
+                "{} does not exist or is not a WebAssembly module",
+                wasm.display()
+            )));
+        }
+    }
+
+    // The replay needs bitcoind, and a height it has actually reached
+    let bitcoind_was_running = manager.is_running(ServiceId::Bitcoind);
+    if let Some(height) = request.truncate_to {
+        if !installed.exists() {
+            return Err(IsomerError::invalid(
+                "No alkanes.wasm installed to replay the kept blocks with",
+            ));
+        }
+        step(progress, "Starting Bitcoin Core");
+        let checked = check_height(manager, height).await;
+        if let Err(e) = checked {
+            restore(manager, &[], bitcoind_was_running, progress).await;
+            return Err(e);
+        }
+    }
+
+    step(progress, "Stopping Metashrew and its dependents");
+    let stopping = manager.handle();
+    let stopped = match run_blocking(move || stopping.stop_with_dependents(service)).await {
+        Ok(stopped) => stopped,
+        Err(e) => {
+            restore(manager, &[], bitcoind_was_running, progress).await;
+            return Err(e);
+        }
+    };
+
+    let staged = runtime_dir.join(format!("{}.new", SWAPPED_WASM_FILE));
+    let rebuilt = rebuild(manager, request, &installed, &staged, timeout, progress).await;
+    if let Err(e) = rebuilt {
+        let _ = run_blocking(move || Ok(std::fs::remove_file(&staged))).await;
+        restore(manager, &stopped, bitcoind_was_running, progress).await;
+        return Err(e);
+    }
+
+    if request.truncate_to.is_some() && !bitcoind_was_running {
+        let stopping = manager.handle();
+        run_blocking(move || stopping.stop_service(ServiceId::Bitcoind)).await?;
+    }
+
+    if stopped.is_empty() {
+        tracing::info!("Metashrew was not running; the new index is built on its next start");
+        step(progress, "Metashrew will reindex on its next start");
+        return Ok(());
+    }
+
+    manager.start_with_progress(&stopped, progress).await?;
+    wait_for_reindex(manager, timeout, progress).await
+}
+
+/// Report a step without block counts
+fn step(progress: ProgressFn<'_>, message: &str) {
+    progress(Progress {
+        completed: 0,
+        total: 0,
+        message: message.to_string(),
+    })
+}
+
+/// Start bitcoind and check it has reached the height to truncate to
+async fn check_height(manager: &ProcessManager, height: u64) -> Result<()> {
+    manager
+        .start_with_dependencies(&[ServiceId::Bitcoind])
+        .await?;
+    let tip = BitcoinRpc::from_config(manager.config())
+        .get_block_count()
+        .await?;
+    if height > tip {
+        return Err(IsomerError::invalid(format!(
+            "Cannot truncate to block {}: the chain is only {} blocks high",
+            height, tip
+        )));
+    }
+    Ok(())
+}
+
+/// With Metashrew stopped: stage the new module and set the index aside, then
+/// build the new index. The old index is deleted only once that succeeds and
+/// put back otherwise.
+async fn rebuild(
+    manager: &mut ProcessManager,
+    request: &ReindexRequest,
+    installed: &Path,
+    staged: &Path,
+    timeout: Duration,
+    progress: ProgressFn<'_>,
+) -> Result<()> {
+    if let Some(wasm) = &request.wasm {
+        step(progress, "Installing alkanes.wasm");
+        let (wasm, staged) = (wasm.clone(), staged.to_path_buf());
+        run_blocking(move || {
+            std::fs::copy(&wasm, &staged)
+                .map(|_| ())
+                .map_err(|e| IsomerError::io(format!("Failed to read {}", wasm.display()), e))
+        })
+        .await?;
+    }
+
+    let runtime_dir = manager.profile().runtime_dir();
+    let db = runtime_dir.join("metashrew");
+    let kept = runtime_dir.join(KEPT_INDEX_DIR);
+    if !(request.wipe || request.truncate_to.is_some()) {
+        return install(manager, request, installed, staged, timeout, progress).await;
+    }
+
+    step(progress, "Setting the Metashrew index aside");
+    let (from, to) = (db.clone(), kept.clone());
+    run_blocking(move || set_index_aside(&from, &to)).await?;
+
+    let rebuilt = install(manager, request, installed, staged, timeout, progress).await;
+    let succeeded = rebuilt.is_ok();
+    let settled = run_blocking(move || {
+        if succeeded {
+            remove_dir(&kept)
+        } else {
+            remove_dir(&db)?;
+            if kept.exists() {
+                std::fs::rename(&kept, &db)
+                    .map_err(|e| IsomerError::io("Failed to put the Metashrew index back", e))?;
+            }
+            Ok(())
+        }
+    })
+    .await;
+    match (rebuilt, settled) {
+        (Err(e), Err(settling)) => {
+            tracing::warn!("Failed to put the Metashrew index back: {}", settling);
+            Err(e)
+        }
+        (Err(e), _) => Err(e),
+        (Ok(()), Err(e)) => {
+            // The new index is in place; only the old copy was left behind
+            tracing::warn!("Failed to delete the previous Metashrew index: {}", e);
+            Ok(())
+        }
+        (Ok(()), Ok(())) => Ok(()),
+    }
+}
+
+/// Move the index out of Metashrew's way, dropping one left by an interrupted rebuild
+fn set_index_aside(db: &Path, kept: &Path) -> Result<()> {
+    remove_dir(kept)?;
+    if db.exists() {
+        std::fs::rename(db, kept)
+            .map_err(|e| IsomerError::io("Failed to set the Metashrew index aside", e))?;
+    }
+    Ok(())
+}
+
+fn remove_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)
+            .map_err(|e| IsomerError::io(format!("Failed to delete {}", dir.display()), e))?;
+    }
+    Ok(())
+}
+
+/// Replay the kept blocks with the current module into the empty index, then
+/// switch the profile to the new module
+async fn install(
+    manager: &mut ProcessManager,
+    request: &ReindexRequest,
+    installed: &Path,
+    staged: &Path,
+    timeout: Duration,
+    progress: ProgressFn<'_>,
+) -> Result<()> {
+    let runtime_dir = manager.profile().runtime_dir();
+    if let Some(height) = request.truncate_to {
+        replay(manager, installed, height, timeout, progress).await?;
+    }
+
+    if request.wasm.is_some() {
+        let swapped = runtime_dir.join(SWAPPED_WASM_FILE);
+        std::fs::rename(staged, &swapped)
+            .map_err(|e| IsomerError::io("Failed to install alkanes.wasm", e))?;
+
+        // Reread the saved config so changes made meanwhile are kept
+        let profile = manager.profile().clone();
+        let mut saved = profile.load_config();
+        saved
+            .binaries
+            .wasm_paths
+            .insert(ServiceId::Metashrew, swapped.clone());
+        profile.save_config(&saved)?;
+
+        let mut config = manager.config().clone();
+        config
+            .binaries
+            .wasm_paths
+            .insert(ServiceId::Metashrew, swapped);
+        manager.update_config(config);
+    }
+    Ok(())
+}
+
+/// Put the stack back after a failed reindex: restart the services that were
+/// stopped, and stop bitcoind again if it was only started for the replay
+async fn restore(
+    manager: &ProcessManager,
+    stopped: &[ServiceId],
+    bitcoind_was_running: bool,
+    progress: ProgressFn<'_>,
+) {
+    if !stopped.is_empty() {
+        step(
+            progress,
+            "Reindex failed, restarting Metashrew and its dependents",
+        );
+        if let Err(e) = manager.start_with_progress(stopped, progress).await {
+            tracing::warn!("Failed to restart services after the reindex failed: {}", e);
+        }
+    }
+    if !bitcoind_was_running && manager.is_running(ServiceId::Bitcoind) {
+        let stopping = manager.handle();
+        if let Err(e) = run_blocking(move || stopping.stop_service(ServiceId::Bitcoind)).await {
+            tracing::warn!(
+                "Failed to stop Bitcoin Core after the reindex failed: {}",
+                e
+            );
+        }
+    }
+}
+
+/// Rebuild the index up to `height` with the previous module
+async fn replay(
+    manager: &ProcessManager,
+    wasm: &Path,
+    height: u64,
+    timeout: Duration,
+    progress: ProgressFn<'_>,
+) -> Result<()> {
+    tracing::info!("Replaying Metashrew up to block {}", height);
+    let mut child = manager.spawn_metashrew_until(wasm, height)?;
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => {
+                return Err(IsomerError::startup(
+                    ServiceId::Metashrew,
+                    format!(
+                        "Metashrew exited with {} while replaying to block {}",
+                        status, height
+                    ),
+                ))
+            }
+            Ok(None) => {}
+            Err(e) => return Err(IsomerError::io("Failed to check the Metashrew replay", e)),
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(IsomerError::timeout(
+                format!("replaying Metashrew to block {}", height),
+                timeout,
+            ));
+        }
+
+        let tip = indexers::get_tip(ServiceId::Metashrew, manager.config()).await;
+        if let Some(indexed) = tip.height {
+            progress(Progress {
+                completed: indexed as usize,
+                total: height as usize,
+                message: format!("Replayed block {} of {}", indexed, height),
+            });
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Follow Metashrew until it has indexed bitcoind's current tip
+async fn wait_for_reindex(
+    manager: &ProcessManager,
+    timeout: Duration,
+    progress: ProgressFn<'_>,
+) -> Result<()> {
+    let config = manager.config();
+    let target = BitcoinRpc::from_config(config).get_block_count().await?;
+    let (tips, synced) = indexers::wait_until(config, &[ServiceId::Metashrew], timeout, |tip| {
+        if let Some(indexed) = tip.height {
+            progress(Progress {
+                completed: indexed as usize,
+                total: target as usize,
+                message: format!("Reindexed block {} of {}", indexed, target),
+            });
+        }
+        tip.height >= Some(target)
+    })
+    .await;
+
+    if synced {
+        tracing::info!("Metashrew reindexed up to block {}", target);
+        return Ok(());
+    }
+    let reached = tips
+        .first()
+        .and_then(|tip| tip.height)
+        .map_or("nothing".to_string(), |h| format!("block {}", h));
+    Err(IsomerError::timeout(
+        format!(
+            "waiting for Metashrew to reindex block {} (reached {})",
+            target, reached
+        ),
+        timeout,
+    ))
+}
@@ -0,0 +1,253 @@
+//! Chain reorganisation simulation
+//!
+//! Replaces the last N blocks with M new ones by invalidating the first block
+//! to disconnect and mining a competing branch on its parent. Transactions
+//! from the disconnected blocks return to the mempool and can be carried over
+//! to the new branch or left out of it. Afterwards we wait for the indexers to
+//! follow the new tip.
+
+use crate::bitcoin_rpc::BitcoinRpc;
+use crate::config::IsomerConfig;
+use crate::error::{IsomerError, Result};
+use crate::indexers::{self, IndexerTip};
+use crate::process_manager::ServiceId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+/// Same limit as `chain::mine_blocks`
+const MAX_BLOCKS: u32 = 1000;
+
+/// What to reorg and how
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReorgRequest {
+    /// Number of blocks to disconnect from the tip
+    pub depth: u32,
+    /// Number of replacement blocks to mine (defaults to `depth`)
+    pub blocks: Option<u32>,
+    /// Address receiving the coinbase of replacement blocks
+    pub address: Option<String>,
+    /// Only these mempool transactions are mined into the new branch (all if unset)
+    pub keep_txids: Option<Vec<String>>,
+    /// Mempool transactions left out of the new branch
+    #[serde(default)]
+    pub drop_txids: Vec<String>,
+    /// How long to wait for indexers to converge (default 60s)
+    pub timeout_secs: Option<u64>,
+}
+
+/// A block on the active chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockRef {
+    pub height: u64,
+    pub hash: String,
+}
+
+/// Outcome of a reorg, also emitted as the `chain-reorg` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorgReport {
+    pub old_tip: BlockRef,
+    pub new_tip: BlockRef,
+    /// Last block shared by the old and new branch
+    pub fork_point: BlockRef,
+    /// Hashes of the blocks that were replaced, lowest first
+    pub disconnected: Vec<String>,
+    /// Hashes of the replacement blocks, lowest first
+    pub connected: Vec<String>,
+    /// Mempool transactions confirmed in the new branch
+    pub mined_txids: Vec<String>,
+    /// Transactions left unconfirmed in the mempool
+    pub dropped_txids: Vec<String>,
+    /// Indexer tips when we stopped waiting
+    pub indexers: Vec<IndexerTip>,
+    /// True if every indexer reached the new tip before the timeout
+    pub converged: bool,
+    /// Time taken for indexers to converge, or the timeout
+    pub wait_ms: u64,
+}
+
+/// Mined transactions and replacement block hashes
+struct NewBranch {
+    connected: Vec<String>,
+    mined_txids: Vec<String>,
+    dropped_txids: Vec<String>,
+}
+
+/// Replace the last `request.depth` blocks and wait for `indexers` to follow
+pub async fn reorg(
+    config: &IsomerConfig,
+    request: &ReorgRequest,
+    default_address: &str,
+    indexers: &[ServiceId],
+) -> Result<ReorgReport> {
+    let blocks = request.blocks.unwrap_or(request.depth);
+    if request.depth == 0 {
+        return Err(IsomerError::invalid("Reorg depth must be at least 1"));
+    }
+    if request.depth > MAX_BLOCKS || blocks > MAX_BLOCKS {
+        return Err(IsomerError::invalid(format!(
+            "Cannot reorg more than {} blocks at once.",
+            MAX_BLOCKS
+        )));
+    }
+    let address = request.address.as_deref().unwrap_or(default_address);
+
+    let rpc = BitcoinRpc::from_config(config);
+    let old_tip = best_block(&rpc).await?;
+    if u64::from(request.depth) > old_tip.height {
+        return Err(IsomerError::invalid(format!(
+            "Cannot reorg {} blocks: the chain is only {} blocks high",
+            request.depth, old_tip.height
+        )));
+    }
+
+    let fork_height = old_tip.height - u64::from(request.depth);
+    let fork_point = BlockRef {
+        height: fork_height,
+        hash: rpc.get_block_hash(fork_height).await?,
+    };
+    let mut disconnected = Vec::with_capacity(request.depth as usize);
+    for height in fork_height + 1..=old_tip.height {
+        disconnected.push(rpc.get_block_hash(height).await?);
+    }
+
+    tracing::info!(
+        "Reorg: replacing {} block(s) above {} with {}",
+        request.depth,
+        fork_height,
+        blocks
+    );
+
+    // A replacement block with the same contents and timestamp as the block it
+    // replaces would hash the same and be rejected as invalid
+    wait_past_block_time(&rpc, &old_tip.hash).await?;
+
+    rpc.invalidate_block(&disconnected[0]).await?;
+
+    let branch = match mine_branch(&rpc, request, blocks, address).await {
+        Ok(branch) => branch,
+        Err(e) => {
+            // Put the original chain back
+            tracing::warn!("Reorg failed, restoring the original chain: {}", e);
+            if let Err(e) = rpc.reconsider_block(&disconnected[0]).await {
+                tracing::error!("Failed to restore the original chain: {}", e);
+            }
+            return Err(e);
+        }
+    };
+
+    let new_tip = best_block(&rpc).await?;
+    tracing::info!(
+        "Reorg: tip moved from {} ({}) to {} ({})",
+        old_tip.height,
+        old_tip.hash,
+        new_tip.height,
+        new_tip.hash
+    );
+
+    let timeout = Duration::from_secs(
+        request
+            .timeout_secs
+            .unwrap_or(indexers::DEFAULT_SYNC_TIMEOUT_SECS),
+    );
+    let started = Instant::now();
+    let (indexers, converged) = indexers::wait_until(config, indexers, timeout, |tip| {
+        tip.is_at(new_tip.height, &new_tip.hash)
+    })
+    .await;
+
+    Ok(ReorgReport {
+        old_tip,
+        new_tip,
+        fork_point,
+        disconnected,
+        connected: branch.connected,
+        mined_txids: branch.mined_txids,
+        dropped_txids: branch.dropped_txids,
+        indexers,
+        converged,
+        wait_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Mine the replacement blocks on top of the fork point
+async fn mine_branch(
+    rpc: &BitcoinRpc,
+    request: &ReorgRequest,
+    blocks: u32,
+    address: &str,
+) -> Result<NewBranch> {
+    // Now includes the transactions from the disconnected blocks
+    let mempool = rpc.get_raw_mempool_verbose().await?;
+
+    for txid in request
+        .keep_txids
+        .iter()
+        .flatten()
+        .chain(&request.drop_txids)
+    {
+        if !mempool.contains_key(txid) {
+            return Err(IsomerError::invalid(format!(
+                "Transaction {} is not in the mempool or the disconnected blocks",
+                txid
+            )));
+        }
+    }
+    let keep: Option<HashSet<&String>> = request.keep_txids.as_ref().map(|k| k.iter().collect());
+    let drop: HashSet<&String> = request.drop_txids.iter().collect();
+    let mut ordered: Vec<String> = mempool
+        .keys()
+        .filter(|txid| keep.as_ref().is_none_or(|keep| keep.contains(txid)))
+        .filter(|txid| !drop.contains(txid))
+        .cloned()
+        .collect();
+
+    // Parents have fewer in-mempool ancestors than their children, so this
+    // is a valid block order
+    ordered.sort_by_key(|txid| mempool[txid].ancestorcount);
+    if blocks == 0 {
+        ordered.clear();
+    }
+    let dropped_txids = mempool
+        .keys()
+        .filter(|txid| !ordered.contains(txid))
+        .cloned()
+        .collect();
+
+    // Put every carried-over transaction in the first block and leave the
+    // rest empty, so dropped transactions are not picked up by later blocks
+    let mut connected = Vec::with_capacity(blocks as usize);
+    for i in 0..blocks {
+        let txids = if i == 0 { ordered.clone() } else { Vec::new() };
+        connected.push(rpc.generate_block(address, &txids).await?);
+    }
+
+    Ok(NewBranch {
+        connected,
+        mined_txids: ordered,
+        dropped_txids,
+    })
+}
+
+async fn best_block(rpc: &BitcoinRpc) -> Result<BlockRef> {
+    let height = rpc.get_block_count().await?;
+    Ok(BlockRef {
+        height,
+        hash: rpc.get_block_hash(height).await?,
+    })
+}
+
+/// Sleep until the wall clock is past a block's timestamp (at most a few seconds)
+async fn wait_past_block_time(rpc: &BitcoinRpc, hash: &str) -> Result<()> {
+    let block_time = rpc.get_block_header(hash).await?.time;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    if block_time >= now {
+        let wait = Duration::from_secs((block_time - now + 1).min(5));
+        tokio::time::sleep(wait).await;
+    }
+    Ok(())
+}
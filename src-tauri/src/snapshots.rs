@@ -0,0 +1,345 @@
+//! Chain snapshots
+//!
+//! A snapshot is a `.tar.gz` holding a manifest, the profile config and the
+//! bitcoin, metashrew, esplora, espo and ord data directories. Services must
+//! be stopped while a snapshot is taken or restored so the databases are
+//! consistent on disk.
+
+use crate::config::{get_data_dir, IsomerConfig};
+use crate::error::{IsomerError, Result};
+use crate::profiles::{self, Profile};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Bumped when the archive layout changes incompatibly
+const SNAPSHOT_FORMAT: u32 = 1;
+
+/// Data directories (relative to the runtime dir) included in a snapshot
+const DATA_DIRS: [&str; 5] = ["bitcoin", "metashrew", "esplora", "espo", "ord"];
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const CONFIG_ENTRY: &str = "config.json";
+const EXTENSION: &str = "tar.gz";
+
+/// Stored as `manifest.json` at the start of the archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub format: u32,
+    pub name: String,
+    /// Unix timestamp (seconds)
+    pub created_at: u64,
+    /// Chain height when the snapshot was taken, if bitcoind was running
+    pub block_height: Option<u64>,
+    /// Profile the snapshot was taken from
+    pub profile: String,
+    pub isomer_version: String,
+}
+
+/// A snapshot file on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub path: String,
+    pub size_bytes: u64,
+    pub manifest: SnapshotManifest,
+}
+
+/// Default directory for snapshots, shared by all profiles
+pub fn snapshots_dir() -> PathBuf {
+    get_data_dir().join("snapshots")
+}
+
+/// Resolve a snapshot name in the snapshots directory, or an explicit file path
+pub fn resolve(name_or_path: &str) -> PathBuf {
+    let path = PathBuf::from(name_or_path);
+    if path.is_file() {
+        return path;
+    }
+    snapshots_dir().join(format!("{}.{}", name_or_path, EXTENSION))
+}
+
+/// Archive a stopped profile's data and config.
+///
+/// Writes to `output` if given, otherwise to `<snapshots_dir>/<name>.tar.gz`.
+pub fn create(
+    profile: &Profile,
+    config: &IsomerConfig,
+    name: &str,
+    block_height: Option<u64>,
+    output: Option<&Path>,
+) -> Result<SnapshotInfo> {
+    profiles::validate_name(name)?;
+
+    let path = match output {
+        Some(path) => path.to_path_buf(),
+        None => snapshots_dir().join(format!("{}.{}", name, EXTENSION)),
+    };
+    if path.exists() {
+        return Err(IsomerError::already_exists(
+            "Snapshot",
+            path.display().to_string(),
+        ));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| IsomerError::io("Failed to create snapshot directory", e))?;
+    }
+
+    let manifest = SnapshotManifest {
+        format: SNAPSHOT_FORMAT,
+        name: name.to_string(),
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        block_height,
+        profile: profile.name.clone(),
+        isomer_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+
+    tracing::info!("Creating snapshot {} at {}", name, path.display());
+
+    // Write to a temporary file so a failed snapshot never looks complete
+    let partial = path.with_extension("partial");
+    if let Err(e) = write_archive(&partial, &manifest, config, &profile.runtime_dir()) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, &path).map_err(|e| IsomerError::io("Failed to save snapshot", e))?;
+
+    info(&path)
+}
+
+fn write_archive(
+    path: &Path,
+    manifest: &SnapshotManifest,
+    config: &IsomerConfig,
+    runtime_dir: &Path,
+) -> Result<()> {
+    let file = File::create(path).map_err(|e| IsomerError::io("Failed to create snapshot", e))?;
+    let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let manifest_json = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| IsomerError::io("Failed to serialize manifest", e))?;
+    let config_json = serde_json::to_vec_pretty(config)
+        .map_err(|e| IsomerError::io("Failed to serialize config", e))?;
+    append_bytes(
+        &mut archive,
+        MANIFEST_ENTRY,
+        &manifest_json,
+        manifest.created_at,
+    )?;
+    append_bytes(
+        &mut archive,
+        CONFIG_ENTRY,
+        &config_json,
+        manifest.created_at,
+    )?;
+
+    for dir in DATA_DIRS {
+        let source = runtime_dir.join(dir);
+        if source.is_dir() {
+            archive
+                .append_dir_all(format!("data/{}", dir), &source)
+                .map_err(|e| {
+                    IsomerError::io(format!("Failed to archive {}", source.display()), e)
+                })?;
+        }
+    }
+
+    archive
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .map_err(|e| IsomerError::io("Failed to finish snapshot", e))?;
+    Ok(())
+}
+
+fn append_bytes<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    entry: &str,
+    bytes: &[u8],
+    mtime: u64,
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, entry, bytes)
+        .map_err(|e| IsomerError::io(format!("Failed to write {}", entry), e))
+}
+
+/// Read the manifest of a snapshot file
+pub fn info(path: &Path) -> Result<SnapshotInfo> {
+    let size_bytes = std::fs::metadata(path)
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                IsomerError::not_found("Snapshot", path.display().to_string())
+            }
+            _ => IsomerError::io(format!("Failed to read snapshot {}", path.display()), e),
+        })?
+        .len();
+
+    let mut archive = open_archive(path)?;
+    let entries = archive
+        .entries()
+        .map_err(|e| IsomerError::io("Failed to read snapshot", e))?;
+
+    // The manifest is written first, so this only decompresses the start of the file
+    for entry in entries {
+        let entry = entry.map_err(|e| IsomerError::io("Failed to read snapshot", e))?;
+        if entry_path(&entry).as_deref() == Some(MANIFEST_ENTRY) {
+            let manifest: SnapshotManifest = serde_json::from_reader(entry)
+                .map_err(|e| IsomerError::invalid(format!("Invalid snapshot manifest: {}", e)))?;
+            return Ok(SnapshotInfo {
+                path: path.display().to_string(),
+                size_bytes,
+                manifest,
+            });
+        }
+    }
+
+    Err(IsomerError::invalid(format!(
+        "{} is not an Isomer snapshot",
+        path.display()
+    )))
+}
+
+/// Snapshots in the snapshots directory, newest first
+pub fn list() -> Vec<SnapshotInfo> {
+    let suffix = format!(".{}", EXTENSION);
+    let mut snapshots: Vec<SnapshotInfo> = std::fs::read_dir(snapshots_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.to_string_lossy().ends_with(&suffix))
+                .filter_map(|path| match info(&path) {
+                    Ok(info) => Some(info),
+                    Err(e) => {
+                        tracing::warn!("Skipping {}: {}", path.display(), e);
+                        None
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.manifest.created_at));
+    snapshots
+}
+
+/// Replace a stopped profile's data directories with those from a snapshot.
+///
+/// Returns the manifest and the snapshot's config with the profile's current
+/// ports kept, so a fixture from another machine does not clash with local ports.
+pub fn restore(
+    profile: &Profile,
+    current: &IsomerConfig,
+    path: &Path,
+) -> Result<(SnapshotManifest, IsomerConfig)> {
+    let manifest = info(path)?.manifest;
+    if manifest.format > SNAPSHOT_FORMAT {
+        return Err(IsomerError::invalid(format!(
+            "Snapshot format {} is newer than this version of Isomer supports",
+            manifest.format
+        )));
+    }
+
+    tracing::info!(
+        "Restoring snapshot {} (height {:?}) into profile {}",
+        manifest.name,
+        manifest.block_height,
+        profile.name
+    );
+
+    // Extract next to the live data first so a broken archive leaves it untouched
+    let staging = profile.root().join(".snapshot-restore");
+    let _ = std::fs::remove_dir_all(&staging);
+    std::fs::create_dir_all(&staging)
+        .map_err(|e| IsomerError::io("Failed to create staging directory", e))?;
+
+    let result = extract(path, &staging).and_then(|config| {
+        let runtime_dir = profile.runtime_dir();
+        std::fs::create_dir_all(&runtime_dir)
+            .map_err(|e| IsomerError::io("Failed to create data directory", e))?;
+
+        for dir in DATA_DIRS {
+            let target = runtime_dir.join(dir);
+            if target.exists() {
+                std::fs::remove_dir_all(&target).map_err(|e| {
+                    IsomerError::io(format!("Failed to remove {}", target.display()), e)
+                })?;
+            }
+            let source = staging.join("data").join(dir);
+            if source.exists() {
+                std::fs::rename(&source, &target).map_err(|e| {
+                    IsomerError::io(format!("Failed to restore {}", target.display()), e)
+                })?;
+            }
+        }
+        Ok(config)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+
+    let mut config = result?;
+    config.ports = current.ports.clone();
+    Ok((manifest, config))
+}
+
+/// Unpack a snapshot into `dir`, returning its config
+fn extract(path: &Path, dir: &Path) -> Result<IsomerConfig> {
+    let mut archive = open_archive(path)?;
+    let entries = archive
+        .entries()
+        .map_err(|e| IsomerError::io("Failed to read snapshot", e))?;
+
+    let mut config = None;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| IsomerError::io("Failed to read snapshot", e))?;
+        match entry_path(&entry).as_deref() {
+            Some(MANIFEST_ENTRY) => {}
+            Some(CONFIG_ENTRY) => {
+                config = Some(serde_json::from_reader(&mut entry).map_err(|e| {
+                    IsomerError::invalid(format!("Invalid snapshot config: {}", e))
+                })?);
+            }
+            Some(p) if p.starts_with("data/") => {
+                // unpack_in refuses paths that escape `dir`
+                entry
+                    .unpack_in(dir)
+                    .map_err(|e| IsomerError::io(format!("Failed to extract {}", p), e))?;
+            }
+            Some(p) => tracing::warn!("Ignoring unexpected snapshot entry {}", p),
+            None => {}
+        }
+    }
+
+    config.ok_or_else(|| IsomerError::invalid("Snapshot does not contain a config"))
+}
+
+/// Delete a snapshot by name or path
+pub fn delete(name_or_path: &str) -> Result<()> {
+    let path = resolve(name_or_path);
+    // Make sure we only ever delete actual snapshots
+    info(&path)?;
+    std::fs::remove_file(&path)
+        .map_err(|e| IsomerError::io(format!("Failed to delete snapshot {}", path.display()), e))
+}
+
+fn open_archive(path: &Path) -> Result<tar::Archive<GzDecoder<File>>> {
+    let file = File::open(path)
+        .map_err(|e| IsomerError::io(format!("Failed to open snapshot {}", path.display()), e))?;
+    Ok(tar::Archive::new(GzDecoder::new(file)))
+}
+
+fn entry_path<R: std::io::Read>(entry: &tar::Entry<R>) -> Option<String> {
+    entry
+        .path()
+        .ok()
+        .map(|p| p.to_string_lossy().trim_start_matches("./").to_string())
+}
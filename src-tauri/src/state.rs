@@ -2,9 +2,19 @@
 //!
 //! Tracks service status, accounts, and runtime data
 
+use crate::accounts;
+use crate::binary_manager::ReleaseManifest;
+use crate::chain;
 use crate::config::IsomerConfig;
-use crate::process_manager::ProcessManager;
+use crate::error::IsomerError;
+use crate::indexers::IndexerStatus;
+use crate::jobs::Jobs;
+use crate::miner::AutoMiner;
+use crate::process_manager::{EventSink, ProcessManager};
+use crate::profiles::Profile;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
 use tauri::Emitter;
 
 /// Status of a managed service
@@ -15,6 +25,8 @@ pub enum ServiceStatus {
     Starting,
     Running,
     Error(String),
+    /// Restarted too many times in a row; the supervisor gave up
+    CrashLoop(String),
 }
 
 /// Information about a single service
@@ -27,6 +39,12 @@ pub struct ServiceInfo {
     pub port: u16,
     pub uptime_secs: Option<u64>,
     pub version: Option<String>,
+    /// Automatic restarts performed by the supervisor
+    pub restarts: u32,
+    /// Last exit reason or startup error
+    pub error: Option<String>,
+    /// Runs a locally built binary or alkanes.wasm
+    pub custom: bool,
 }
 
 /// Pre-funded development account
@@ -35,6 +53,7 @@ pub struct Account {
     pub index: usize,
     pub address: String,
     pub private_key: String,
+    pub derivation_path: String,
     pub balance_sats: u64,
 }
 
@@ -45,6 +64,8 @@ pub struct SystemStatus {
     pub block_height: u64,
     pub mempool_size: usize,
     pub is_ready: bool,
+    /// Tip height and lag of each running indexer
+    pub indexers: Vec<IndexerStatus>,
 }
 
 /// Main application state
@@ -52,25 +73,51 @@ pub struct AppState {
     pub config: IsomerConfig,
     pub process_manager: ProcessManager,
     pub accounts: Vec<Account>,
+    pub miner: AutoMiner,
+    /// Background start/stop/reset operations
+    pub jobs: Jobs,
     pub block_height: u64,
     pub mempool_size: usize,
+    /// Last fetched release manifest, reused for update checks until it expires
+    pub release_manifest: Option<(Instant, ReleaseManifest)>,
     app_handle: tauri::AppHandle,
 }
 
 impl AppState {
     pub fn new(app_handle: tauri::AppHandle) -> Self {
+        let profile = Profile::active();
+        let mut config = profile.load_config();
+        if let Err(e) = accounts::ensure_mnemonic(&mut config) {
+            tracing::error!("{}", e);
+        }
+        let accounts = accounts::accounts_for_config(&config);
+
+        // Clean up any orphaned processes from previous runs
+        ProcessManager::kill_orphans(&config);
+        let mut process_manager = ProcessManager::new(profile, &config);
+        let events_handle = app_handle.clone();
+        let events: EventSink = Arc::new(move |event, payload| {
+            if let Err(e) = events_handle.emit(event, payload) {
+                tracing::error!("Failed to emit event {}: {}", event, e);
+            }
+        });
+        process_manager.set_event_sink(events.clone());
+
         Self {
-            config: IsomerConfig::load(),
-            process_manager: ProcessManager::new(),
-            accounts: Vec::new(),
+            process_manager,
+            config,
+            accounts,
+            miner: AutoMiner::new(),
+            jobs: Jobs::new(events),
             block_height: 0,
             mempool_size: 0,
+            release_manifest: None,
             app_handle,
         }
     }
 
     /// Get the current system status
-    pub fn get_status(&mut self) -> SystemStatus {
+    pub fn get_status(&self) -> SystemStatus {
         let services = self.process_manager.get_all_status();
         let is_ready = services.iter().all(|s| s.status == "running");
 
@@ -79,7 +126,47 @@ impl AppState {
             block_height: self.block_height,
             mempool_size: self.mempool_size,
             is_ready,
+            indexers: Vec::new(),
+        }
+    }
+
+    /// Stop everything and make another profile active
+    pub fn switch_profile(&mut self, profile: Profile) -> Result<(), IsomerError> {
+        if !profile.exists() {
+            return Err(IsomerError::not_found("Profile", profile.name));
         }
+
+        self.miner.stop();
+        self.process_manager.stop_all()?;
+        profile.activate()?;
+
+        let mut config = profile.load_config();
+        // Runs after activation so a generated mnemonic is saved to the new profile
+        accounts::ensure_mnemonic(&mut config)?;
+
+        self.accounts = accounts::accounts_for_config(&config);
+        self.process_manager
+            .switch_profile(profile, config.clone())?;
+        self.config = config;
+        self.block_height = 0;
+        self.mempool_size = 0;
+        Ok(())
+    }
+
+    /// Start the auto-miner, paying block rewards to the first dev account
+    pub fn start_auto_miner(&mut self) {
+        let address = self
+            .accounts
+            .first()
+            .map(|a| a.address.clone())
+            .unwrap_or_else(|| chain::DEFAULT_MINING_ADDRESS.to_string());
+
+        let app_handle = self.app_handle.clone();
+        self.miner.start(&self.config, address, move |block| {
+            if let Err(e) = app_handle.emit("block-mined", block) {
+                tracing::error!("Failed to emit event block-mined: {}", e);
+            }
+        });
     }
 
     /// Emit an event to the frontend
@@ -0,0 +1,343 @@
+//! Per-service supervisor threads
+//!
+//! Each running service is owned by a supervisor thread that waits on the
+//! child process and applies the configured `RestartPolicy` when it exits,
+//! with exponential backoff and a crash-loop limit.
+
+use crate::config::{IsomerConfig, RestartPolicy};
+use crate::process_manager::{EventSink, LogBuffer, ProcessManager, ServiceId};
+use crate::profiles::Profile;
+use crate::state::ServiceStatus;
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// A service that stays up this long has its consecutive failure count reset
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+/// How long a service gets to exit after SIGTERM before it is killed
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// State shared between the `ProcessManager` and a supervisor thread
+#[derive(Debug, Clone)]
+pub struct SupervisedState {
+    pub pid: Option<u32>,
+    pub started_at: Instant,
+    pub status: ServiceStatus,
+    /// Total automatic restarts since the service was started
+    pub restarts: u32,
+    /// Most recent exit reason or error
+    pub last_error: Option<String>,
+    stop_requested: bool,
+}
+
+/// Everything a supervisor needs to respawn its service
+pub struct SpawnContext {
+    pub config: IsomerConfig,
+    pub profile: Profile,
+    pub log_buffer: LogBuffer,
+    pub events: Option<EventSink>,
+}
+
+impl SpawnContext {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        if let Some(ref events) = self.events {
+            events(event, payload);
+        }
+    }
+}
+
+/// Handle to a service's supervisor thread
+pub struct Supervisor {
+    shared: Arc<Mutex<SupervisedState>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Take ownership of a freshly spawned child and start supervising it
+    pub fn spawn(service: ServiceId, child: Child, ctx: SpawnContext) -> Self {
+        let shared = Arc::new(Mutex::new(SupervisedState {
+            pid: Some(child.id()),
+            started_at: Instant::now(),
+            status: ServiceStatus::Starting,
+            restarts: 0,
+            last_error: None,
+            stop_requested: false,
+        }));
+
+        let thread_shared = shared.clone();
+        let thread = std::thread::Builder::new()
+            .name(format!("supervisor-{}", service.id()))
+            .spawn(move || Self::run(service, child, ctx, thread_shared))
+            .expect("failed to spawn supervisor thread");
+
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Snapshot of the supervised process state
+    pub fn state(&self) -> SupervisedState {
+        self.shared.lock().unwrap().clone()
+    }
+
+    pub fn set_status(&self, status: ServiceStatus) {
+        let mut state = self.shared.lock().unwrap();
+        if let ServiceStatus::Error(ref e) = status {
+            state.last_error = Some(e.clone());
+        }
+        state.status = status;
+    }
+
+    /// Whether the supervisor has given up (process exited and will not be restarted)
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|t| t.is_finished())
+    }
+
+    /// Stop the process and its supervisor, escalating to SIGKILL after a grace period
+    pub fn stop(mut self, service: ServiceId) -> Result<(), String> {
+        let pid = {
+            let mut state = self.shared.lock().unwrap();
+            state.stop_requested = true;
+            state.pid
+        };
+
+        if let Some(pid) = pid {
+            // Try graceful shutdown first (SIGTERM on Unix)
+            #[cfg(unix)]
+            unsafe {
+                libc::kill(pid as i32, libc::SIGTERM);
+            }
+
+            #[cfg(windows)]
+            ProcessManager::force_kill(pid);
+
+            let deadline = Instant::now() + STOP_GRACE_PERIOD;
+            while !self.is_finished() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+
+            if !self.is_finished() {
+                tracing::warn!(
+                    "{} did not exit within {}s, killing",
+                    service.display_name(),
+                    STOP_GRACE_PERIOD.as_secs()
+                );
+                ProcessManager::force_kill(pid);
+            }
+        }
+
+        match self.thread.take().map(|t| t.join()) {
+            Some(Err(_)) => Err(format!(
+                "Supervisor for {} panicked",
+                service.display_name()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn run(
+        service: ServiceId,
+        mut child: Child,
+        ctx: SpawnContext,
+        shared: Arc<Mutex<SupervisedState>>,
+    ) {
+        let restart = &ctx.config.restart;
+        let policy = restart.policy_for(service);
+        let mut failures: u32 = 0;
+
+        loop {
+            let exit = child.wait();
+            let success = matches!(exit, Ok(ref status) if status.success());
+            let reason = match exit {
+                Ok(status) => format!("exited ({})", status),
+                Err(e) => format!("could not be waited on: {}", e),
+            };
+
+            let ran_for = {
+                let mut state = shared.lock().unwrap();
+                state.pid = None;
+                if state.stop_requested {
+                    state.status = ServiceStatus::Stopped;
+                    tracing::info!("{} stopped: {}", service.display_name(), reason);
+                    return;
+                }
+                state.started_at.elapsed()
+            };
+
+            tracing::warn!("{} {}", service.display_name(), reason);
+
+            let should_restart = match policy {
+                RestartPolicy::Never => false,
+                RestartPolicy::OnFailure => !success,
+                RestartPolicy::Always => true,
+            };
+
+            if !should_restart {
+                let mut state = shared.lock().unwrap();
+                if success {
+                    state.status = ServiceStatus::Stopped;
+                } else {
+                    state.status = ServiceStatus::Error(reason.clone());
+                    state.last_error = Some(reason);
+                }
+                return;
+            }
+
+            if ran_for >= STABLE_UPTIME {
+                failures = 0;
+            }
+
+            if failures >= restart.max_restarts {
+                let message = format!(
+                    "{} {} after {} consecutive restarts",
+                    service.display_name(),
+                    reason,
+                    failures
+                );
+                tracing::error!("{}, giving up", message);
+
+                let restarts = {
+                    let mut state = shared.lock().unwrap();
+                    state.status = ServiceStatus::CrashLoop(message.clone());
+                    state.last_error = Some(message.clone());
+                    state.restarts
+                };
+                ctx.emit(
+                    "service-crash-loop",
+                    serde_json::json!({
+                        "service": service,
+                        "restarts": restarts,
+                        "error": message,
+                    }),
+                );
+                return;
+            }
+
+            let delay = restart.backoff(failures);
+            failures += 1;
+
+            {
+                let mut state = shared.lock().unwrap();
+                state.status = ServiceStatus::Starting;
+                state.last_error = Some(reason.clone());
+            }
+            tracing::info!(
+                "Restarting {} in {}ms (attempt {}/{})",
+                service.display_name(),
+                delay.as_millis(),
+                failures,
+                restart.max_restarts
+            );
+            ctx.emit(
+                "service-restarting",
+                serde_json::json!({
+                    "service": service,
+                    "attempt": failures,
+                    "delay_ms": delay.as_millis() as u64,
+                    "reason": reason,
+                }),
+            );
+
+            if !Self::sleep_unless_stopped(&shared, delay) {
+                shared.lock().unwrap().status = ServiceStatus::Stopped;
+                return;
+            }
+
+            child = match ProcessManager::spawn_child(
+                service,
+                &ctx.config,
+                &ctx.profile,
+                &ctx.log_buffer,
+            ) {
+                Ok(child) => child,
+                Err(e) => {
+                    tracing::error!("{}", e);
+                    let mut state = shared.lock().unwrap();
+                    state.status = ServiceStatus::Error(e.to_string());
+                    state.last_error = Some(e.to_string());
+                    return;
+                }
+            };
+
+            {
+                let mut state = shared.lock().unwrap();
+                if state.stop_requested {
+                    // Stop was requested while we were spawning
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    state.status = ServiceStatus::Stopped;
+                    return;
+                }
+                state.pid = Some(child.id());
+                state.started_at = Instant::now();
+                state.restarts += 1;
+            }
+
+            Self::wait_until_ready(service, &mut child, &ctx.config, &shared);
+        }
+    }
+
+    /// Sleep in short steps so a stop request is not delayed by a long backoff.
+    /// Returns false if a stop was requested.
+    fn sleep_unless_stopped(shared: &Arc<Mutex<SupervisedState>>, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            if shared.lock().unwrap().stop_requested {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        !shared.lock().unwrap().stop_requested
+    }
+
+    /// Mark a restarted service `Running` once it passes a health check
+    fn wait_until_ready(
+        service: ServiceId,
+        child: &mut Child,
+        config: &IsomerConfig,
+        shared: &Arc<Mutex<SupervisedState>>,
+    ) {
+        // This is a plain thread, so it needs its own runtime for the async probe
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                tracing::warn!("Cannot health-check {}: {}", service.display_name(), e);
+                return;
+            }
+        };
+
+        let deadline = Instant::now() + service.startup_timeout();
+        loop {
+            if shared.lock().unwrap().stop_requested {
+                return;
+            }
+            // Exited again; the supervisor loop handles it
+            if matches!(child.try_wait(), Ok(Some(_))) {
+                return;
+            }
+            if runtime.block_on(ProcessManager::probe_health(service, config)) {
+                shared.lock().unwrap().status = ServiceStatus::Running;
+                tracing::info!("{} is healthy again", service.display_name());
+                return;
+            }
+            if Instant::now() >= deadline {
+                let e = format!(
+                    "{} did not become healthy within {}s",
+                    service.display_name(),
+                    service.startup_timeout().as_secs()
+                );
+                let mut state = shared.lock().unwrap();
+                state.status = ServiceStatus::Error(e.clone());
+                state.last_error = Some(e);
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+}
@@ -3,15 +3,18 @@ import { ServiceCard } from './ServiceCard';
 import { MiningPanel } from './MiningPanel';
 import { FaucetPanel } from './FaucetPanel';
 import { useStore } from '../lib/store';
-import { api } from '../lib/api';
+import { api, errorMessage, isIsomerError, waitForJob } from '../lib/api';
 import { useBinaries } from '../hooks/useStatus';
 
 export function Dashboard() {
     const { status, error, setError } = useStore();
     const [isStarting, setIsStarting] = useState(false);
     const [isStopping, setIsStopping] = useState(false);
+    const [jobMessage, setJobMessage] = useState<string | null>(null);
     const [copied, setCopied] = useState(false);
-    const { binaries, checkBinaries } = useBinaries();
+    const [needsBinaries, setNeedsBinaries] = useState(false);
+    const [isDownloading, setIsDownloading] = useState(false);
+    const { binaries, checkBinaries, downloadBinaries } = useBinaries();
 
     useEffect(() => {
         checkBinaries();
@@ -22,24 +25,43 @@ export function Dashboard() {
     const handleStart = async () => {
         setIsStarting(true);
         setError(null);
+        setNeedsBinaries(false);
         try {
-            await api.startServices();
+            const job = await api.startServices();
+            await waitForJob(job, (j) => setJobMessage(j.progress?.message ?? null));
         } catch (err) {
             console.error('Failed to start services:', err);
-            setError(err instanceof Error ? err.message : String(err));
+            setError(errorMessage(err));
+            setNeedsBinaries(isIsomerError(err, 'binary_not_found'));
         } finally {
             setIsStarting(false);
+            setJobMessage(null);
+        }
+    };
+
+    const handleDownload = async () => {
+        setIsDownloading(true);
+        setError(null);
+        setNeedsBinaries(false);
+        try {
+            // Failures are reported through the store's error
+            await downloadBinaries();
+        } finally {
+            setIsDownloading(false);
         }
     };
 
     const handleStop = async () => {
         setIsStopping(true);
         try {
-            await api.stopServices();
+            const job = await api.stopServices();
+            await waitForJob(job, (j) => setJobMessage(j.progress?.message ?? null));
         } catch (err) {
             console.error('Failed to stop services:', err);
+            setError(errorMessage(err));
         } finally {
             setIsStopping(false);
+            setJobMessage(null);
         }
     };
 
@@ -66,7 +88,7 @@ export function Dashboard() {
                 <div>
                     <h1 className="text-2xl font-bold text-white">Dashboard</h1>
                     <p className="text-zinc-400 mt-1">
-                        {allRunning ? 'All services running' : allStopped ? 'Services stopped' : 'Services partially running'}
+                        {jobMessage ?? (allRunning ? 'All services running' : allStopped ? 'Services stopped' : 'Services partially running')}
                     </p>
                 </div>
 
@@ -96,8 +118,18 @@ export function Dashboard() {
 
             {/* Error banner */}
             {error && (
-                <div className="bg-red-600/20 border border-red-600/50 rounded-lg px-4 py-3 text-red-400">
-                    {error}
+                <div className="bg-red-600/20 border border-red-600/50 rounded-lg px-4 py-3 text-red-400 flex items-center justify-between gap-4">
+                    <span>{error}</span>
+                    {needsBinaries && (
+                        <button
+                            onClick={handleDownload}
+                            disabled={isDownloading}
+                            className="px-3 py-1.5 bg-indigo-600 hover:bg-indigo-500 disabled:opacity-50 
+                               rounded-lg text-white text-sm font-medium transition-colors shrink-0"
+                        >
+                            {isDownloading ? 'Downloading...' : 'Download binaries'}
+                        </button>
+                    )}
                 </div>
             )}
 
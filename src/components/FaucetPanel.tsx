@@ -1,5 +1,5 @@
 import { useState } from 'react';
-import { api } from '../lib/api';
+import { api, errorMessage } from '../lib/api';
 
 interface FaucetPanelProps {
     disabled?: boolean;
@@ -26,7 +26,7 @@ export function FaucetPanel({ disabled }: FaucetPanelProps) {
             setAddress('');
         } catch (err) {
             console.error('Faucet error:', err);
-            setResult({ success: false, message: err instanceof Error ? err.message : String(err) });
+            setResult({ success: false, message: errorMessage(err) });
         } finally {
             setIsSending(false);
         }
@@ -1,6 +1,7 @@
 import { useState, useEffect, useRef } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { api } from '../lib/api';
-import type { LogEntry } from '../lib/types';
+import type { LogBatch, LogEntry, LogLevel } from '../lib/types';
 
 const SERVICE_COLORS: Record<string, string> = {
     'bitcoind': 'bg-orange-600/20 text-orange-400 border-orange-600/50',
@@ -13,6 +14,17 @@ const SERVICE_COLORS: Record<string, string> = {
 
 const ALL_SERVICES = ['bitcoind', 'metashrew', 'ord', 'esplora', 'espo', 'jsonrpc'];
 
+const LEVEL_COLORS: Record<LogLevel, string> = {
+    'trace': 'text-zinc-500',
+    'debug': 'text-zinc-400',
+    'info': 'text-zinc-300',
+    'warn': 'text-amber-400',
+    'error': 'text-red-400',
+};
+
+// Matches the size of the backend's in-memory log buffer
+const MAX_LOGS = 1000;
+
 export function LogsPanel() {
     const [enabledServices, setEnabledServices] = useState<Set<string>>(new Set(ALL_SERVICES));
     const [logs, setLogs] = useState<LogEntry[]>([]);
@@ -53,20 +65,34 @@ export function LogsPanel() {
         }
     };
 
-    // Poll for logs
+    // Stream logs pushed by the backend, resuming from the last seen cursor
     useEffect(() => {
-        const fetchLogs = async () => {
-            try {
-                const newLogs = await api.getLogs(undefined, 500);
-                setLogs(newLogs);
-            } catch (err) {
-                console.error('Failed to fetch logs:', err);
+        let subscriptionId: number | null = null;
+        let cancelled = false;
+
+        const unlisten = listen<LogBatch>('log-entry', (event) => {
+            if (event.payload.subscription !== subscriptionId) return;
+            setLogs(prev => [...prev, ...event.payload.entries].slice(-MAX_LOGS));
+        });
+
+        api.subscribeLogs()
+            .then((subscription) => {
+                if (cancelled) {
+                    api.unsubscribeLogs(subscription.id);
+                    return;
+                }
+                subscriptionId = subscription.id;
+                setLogs(subscription.backlog.slice(-MAX_LOGS));
+            })
+            .catch((err) => console.error('Failed to subscribe to logs:', err));
+
+        return () => {
+            cancelled = true;
+            unlisten.then((fn) => fn());
+            if (subscriptionId !== null) {
+                api.unsubscribeLogs(subscriptionId);
             }
         };
-
-        fetchLogs();
-        const interval = setInterval(fetchLogs, 1000);
-        return () => clearInterval(interval);
     }, []);
 
     // Filter logs by enabled services
@@ -185,7 +211,7 @@ export function LogsPanel() {
                         {filteredLogs.map((log, index) => (
                             <div
                                 key={`${log.timestamp}-${index}`}
-                                className={`flex gap-2 py-0.5 ${log.is_stderr ? 'text-amber-400' : 'text-zinc-300'}`}
+                                className={`flex gap-2 py-0.5 ${LEVEL_COLORS[log.level]}`}
                             >
                                 <span className="text-zinc-600 shrink-0">{formatTime(log.timestamp)}</span>
                                 <span className={`shrink-0 px-1.5 rounded text-[10px] font-medium ${SERVICE_COLORS[log.service]?.replace('border-', 'border border-') || 'bg-zinc-700 text-zinc-400'
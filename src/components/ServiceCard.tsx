@@ -53,7 +53,7 @@ export function ServiceCard({ service }: ServiceCardProps) {
                     </div>
                     <div>
                         <span className="text-zinc-500 block mb-1">Version</span>
-                        <p className="text-zinc-300 font-mono truncate" title={service.version || ''}>{service.version || 'unknown'}</p>
+                        <p className="text-zinc-300 font-mono truncate" title={service.version || ''}>{service.version || 'unknown'}{service.custom && ' (custom)'}</p>
                     </div>
                     <div>
                         <span className="text-zinc-500 block mb-1">PID</span>
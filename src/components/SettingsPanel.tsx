@@ -1,7 +1,6 @@
 import { useState } from 'react';
-import { invoke } from '@tauri-apps/api/core';
-
 import { useBinaries } from '../hooks/useStatus';
+import { api, errorMessage, waitForJob } from '../lib/api';
 
 export function SettingsPanel() {
     const [isResetting, setIsResetting] = useState(false);
@@ -35,20 +34,33 @@ export function SettingsPanel() {
         setResetInput('');
 
         try {
-            await invoke('reset_chain');
-
-            await invoke('reset_chain');
+            const job = await api.resetChain();
+            await waitForJob(job, (j) => {
+                if (j.progress) setStatus(`${j.progress.message}...`);
+            });
 
             setStatus('Chain reset complete! Services are stopped.');
             setTimeout(() => setStatus(null), 5000);
         } catch (error) {
             console.error('Failed to reset chain:', error);
-            setStatus(`Error: ${error}`);
+            setStatus(`Error: ${errorMessage(error)}`);
         } finally {
             setIsResetting(false);
         }
     };
 
+    const handleRollback = async (id: string) => {
+        setIsDownloading(true);
+        try {
+            await api.rollbackBinary(id);
+            await checkBinaries();
+        } catch (err) {
+            console.error('Failed to roll back binary:', err);
+        } finally {
+            setIsDownloading(false);
+        }
+    };
+
     const missingBinaries = binaries.some(b => b.status === 'notinstalled');
     const updateAvailable = binaries.some(b => typeof b.status === 'object' && 'updateavailable' in b.status);
 
@@ -150,6 +162,49 @@ export function SettingsPanel() {
                                             : 'Check for Updates'}
                             </button>
                         </div>
+
+                        {binaries.some(b => b.can_roll_back || (typeof b.status === 'object' && 'updateavailable' in b.status)) && (
+                            <ul className="mt-4 space-y-2">
+                                {binaries.map(b => {
+                                    const update = typeof b.status === 'object' && 'updateavailable' in b.status
+                                        ? b.status.updateavailable
+                                        : null;
+                                    if (!update && !b.can_roll_back) return null;
+                                    return (
+                                        <li key={b.id} className="flex items-center justify-between text-sm">
+                                            <div className="text-zinc-300">
+                                                {b.service}
+                                                {update && (
+                                                    <span className="text-zinc-400">
+                                                        {' '}{update.current} → {update.latest}
+                                                        {update.changelog_urls.map(url => (
+                                                            <a
+                                                                key={url}
+                                                                href={url}
+                                                                target="_blank"
+                                                                rel="noopener noreferrer"
+                                                                className="ml-2 text-indigo-400 hover:text-indigo-300"
+                                                            >
+                                                                Release notes
+                                                            </a>
+                                                        ))}
+                                                    </span>
+                                                )}
+                                            </div>
+                                            {b.can_roll_back && (
+                                                <button
+                                                    onClick={() => handleRollback(b.id)}
+                                                    disabled={isDownloading}
+                                                    className="px-3 py-1 rounded-lg text-zinc-300 bg-zinc-800 hover:bg-zinc-700 disabled:opacity-50"
+                                                >
+                                                    Roll back
+                                                </button>
+                                            )}
+                                        </li>
+                                    );
+                                })}
+                            </ul>
+                        )}
                     </div>
                 </section>
 
@@ -1,6 +1,6 @@
 import { useEffect } from "react";
 import { listen } from "@tauri-apps/api/event";
-import { api } from "../lib/api";
+import { api, errorMessage } from "../lib/api";
 import { useStore } from "../lib/store";
 
 /**
@@ -38,7 +38,7 @@ export function useSystemStatus(pollInterval = 2000) {
         }
       } catch (err) {
         if (mounted) {
-          setError(err instanceof Error ? err.message : "Failed to get status");
+          setError(errorMessage(err));
         }
       }
     };
@@ -82,7 +82,7 @@ export function useBinaries() {
       const result = await api.checkBinaries();
       setBinaries(result);
     } catch (err) {
-      setError(err instanceof Error ? err.message : "Failed to check binaries");
+      setError(errorMessage(err));
     } finally {
       setLoading(false);
     }
@@ -94,9 +94,7 @@ export function useBinaries() {
       await api.downloadBinaries();
       await checkBinaries(); // Refresh status after download
     } catch (err) {
-      setError(
-        err instanceof Error ? err.message : "Failed to download binaries"
-      );
+      setError(errorMessage(err));
     } finally {
       setLoading(false);
     }
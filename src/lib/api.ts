@@ -1,10 +1,27 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import type {
   SystemStatus,
   Account,
   BinaryInfo,
+  BinaryVersion,
+  IndexerStatus,
   IsomerConfig,
+  IsomerError,
+  IsomerErrorKind,
+  Job,
   LogEntry,
+  LogFilter,
+  LogSearch,
+  LogSubscription,
+  MiningConfig,
+  AutoMineStatus,
+  PortConflict,
+  ProfileInfo,
+  ReindexRequest,
+  ReorgReport,
+  ReorgRequest,
+  SnapshotInfo,
 } from "./types";
 
 /**
@@ -17,19 +34,45 @@ export const api = {
   getStatus: () => invoke<SystemStatus>("get_status"),
 
   /**
-   * Start all services
+   * Start all services as a background job (see waitForJob)
    */
-  startServices: () => invoke<void>("start_services"),
+  startServices: () => invoke<Job>("start_services"),
 
   /**
-   * Stop all services
+   * Stop all services as a background job
    */
-  stopServices: () => invoke<void>("stop_services"),
+  stopServices: () => invoke<Job>("stop_services"),
 
   /**
-   * Reset chain - stops services and clears all data
+   * Start one service, starting missing dependencies first
    */
-  resetChain: () => invoke<void>("reset_chain"),
+  startService: (service: string) => invoke<Job>("start_service", { service }),
+
+  /**
+   * Stop one service and everything that depends on it
+   */
+  stopService: (service: string) => invoke<Job>("stop_service", { service }),
+
+  /**
+   * Restart one service along with its running dependents
+   */
+  restartService: (service: string) =>
+    invoke<Job>("restart_service", { service }),
+
+  /**
+   * Reset chain - stops services and clears all data, as a background job
+   */
+  resetChain: () => invoke<Job>("reset_chain"),
+
+  /**
+   * Get a background job by id
+   */
+  getJob: (id: number) => invoke<Job>("get_job", { id }),
+
+  /**
+   * Recent background jobs, newest first
+   */
+  listJobs: () => invoke<Job[]>("list_jobs"),
 
   /**
    * Faucet - send BTC from dev wallet to any address
@@ -43,6 +86,36 @@ export const api = {
   getLogs: (service?: string, limit?: number) =>
     invoke<LogEntry[]>("get_logs", { service, limit }),
 
+  /**
+   * Subscribe to live logs; new entries arrive as `log-entry` events.
+   * Pass the last seen cursor to resume without gaps.
+   */
+  subscribeLogs: (filter?: LogFilter, cursor?: number) =>
+    invoke<LogSubscription>("subscribe_logs", { filter, cursor }),
+
+  /**
+   * Cancel a log subscription
+   */
+  unsubscribeLogs: (id: number) => invoke<void>("unsubscribe_logs", { id }),
+
+  /**
+   * Read persisted logs, including rotated files from earlier sessions.
+   * `since`/`until` are Unix timestamps in seconds.
+   */
+  readLogHistory: (
+    service?: string,
+    since?: number,
+    until?: number,
+    limit?: number
+  ) =>
+    invoke<LogEntry[]>("read_log_history", { service, since, until, limit }),
+
+  /**
+   * Search persisted logs by regex, level, service and time range
+   */
+  searchLogs: (query: LogSearch) =>
+    invoke<LogEntry[]>("search_logs", { query }),
+
   /**
    * Clear all logs
    */
@@ -54,6 +127,48 @@ export const api = {
   mineBlocks: (count: number, address?: string) =>
     invoke<number>("mine_blocks", { count, address }),
 
+  /**
+   * Wait until every running indexer has indexed `height`
+   * (bitcoind's current height if omitted)
+   */
+  waitForSync: (height?: number, timeoutSecs?: number) =>
+    invoke<IndexerStatus[]>("wait_for_sync", { height, timeoutSecs }),
+
+  /**
+   * Replace the last blocks with a competing branch and wait for the
+   * indexers to follow. Also emitted as a `chain-reorg` event.
+   */
+  reorg: (request: ReorgRequest) =>
+    invoke<ReorgReport>("reorg", { request }),
+
+  /**
+   * Swap the alkanes.wasm Metashrew loads and rebuild its index, as a
+   * background job; progress reports the reindexed block height
+   */
+  reindexMetashrew: (request: ReindexRequest) =>
+    invoke<Job>("reindex_metashrew", { request }),
+
+  /**
+   * Start the background auto-miner
+   */
+  startAutoMine: () => invoke<AutoMineStatus>("start_auto_mine"),
+
+  /**
+   * Stop the background auto-miner
+   */
+  stopAutoMine: () => invoke<AutoMineStatus>("stop_auto_mine"),
+
+  /**
+   * Save mining settings and apply them to the auto-miner
+   */
+  configureAutoMine: (settings: MiningConfig) =>
+    invoke<AutoMineStatus>("configure_auto_mine", { settings }),
+
+  /**
+   * Get auto-miner state
+   */
+  getAutoMineStatus: () => invoke<AutoMineStatus>("get_auto_mine_status"),
+
   /**
    * Get pre-funded accounts
    */
@@ -69,6 +184,56 @@ export const api = {
    */
   downloadBinaries: () => invoke<void>("download_binaries"),
 
+  /**
+   * Known and installed versions of a service binary
+   */
+  listBinaryVersions: (service: string) =>
+    invoke<BinaryVersion[]>("list_binary_versions", { service }),
+
+  /**
+   * Download a version of a service binary next to the installed ones
+   */
+  installBinaryVersion: (service: string, version: string) =>
+    invoke<void>("install_binary_version", { service, version }),
+
+  /**
+   * Pin the version a service runs in the active profile (null to unpin)
+   */
+  setBinaryVersion: (service: string, version: string | null) =>
+    invoke<void>("set_binary_version", { service, version }),
+
+  /**
+   * Run a locally built binary for a service in the active profile (null to clear)
+   */
+  setBinaryPath: (service: string, path: string | null) =>
+    invoke<void>("set_binary_path", { service, path }),
+
+  /**
+   * Load a locally built alkanes.wasm for a service (null to clear)
+   */
+  setWasmPath: (service: string, path: string | null) =>
+    invoke<void>("set_wasm_path", { service, path }),
+
+  /**
+   * Install the newest release of a service or of `alkanes-wasm`, keeping the
+   * current one for rollback. Resolves to the new version.
+   */
+  upgradeBinary: (service: string) =>
+    invoke<string>("upgrade_binary", { service }),
+
+  /**
+   * Undo the last upgrade of a stopped service or of `alkanes-wasm`.
+   * Resolves to the version active again.
+   */
+  rollbackBinary: (service: string) =>
+    invoke<string | null>("rollback_binary", { service }),
+
+  /**
+   * Install missing binaries from an offline bundle directory or .tar.gz
+   */
+  installBinariesFromBundle: (path: string) =>
+    invoke<void>("install_binaries_from_bundle", { path }),
+
   /**
    * Get current configuration
    */
@@ -85,6 +250,130 @@ export const api = {
    */
   checkServiceHealth: (service: string) =>
     invoke<boolean>("check_service_health", { service }),
+
+  /**
+   * Report processes occupying configured service ports
+   */
+  checkPorts: () => invoke<PortConflict[]>("check_ports"),
+
+  /**
+   * List chain profiles
+   */
+  listProfiles: () => invoke<ProfileInfo[]>("list_profiles"),
+
+  /**
+   * Create a profile with default settings
+   */
+  createProfile: (name: string) =>
+    invoke<ProfileInfo>("create_profile", { name }),
+
+  /**
+   * Copy a profile's config and chain data into a new profile as a background
+   * job. `profile-cloned` is emitted once the copy is complete.
+   */
+  cloneProfile: (source: string, name: string) =>
+    invoke<Job>("clone_profile", { source, name }),
+
+  /**
+   * Stop all services and switch to another profile as a background job.
+   * `profile-switched` is emitted once it is active.
+   */
+  switchProfile: (name: string) => invoke<Job>("switch_profile", { name }),
+
+  /**
+   * Delete an inactive profile and its data
+   */
+  deleteProfile: (name: string) => invoke<void>("delete_profile", { name }),
+
+  /**
+   * Snapshot chain data and config of the active profile as a background job.
+   * Running services are restarted afterwards.
+   */
+  createSnapshot: (name: string, output?: string) =>
+    invoke<Job>("create_snapshot", { name, output }),
+
+  /**
+   * List snapshots in the snapshots directory
+   */
+  listSnapshots: () => invoke<SnapshotInfo[]>("list_snapshots"),
+
+  /**
+   * Stop services and restore a snapshot (by name or file path) as a background job
+   */
+  restoreSnapshot: (snapshot: string) =>
+    invoke<Job>("restore_snapshot", { snapshot }),
+
+  /**
+   * Delete a snapshot (by name or file path)
+   */
+  deleteSnapshot: (snapshot: string) =>
+    invoke<void>("delete_snapshot", { snapshot }),
 };
 
+/**
+ * Wait for a background job to finish. Resolves with the finished job, or
+ * rejects with its IsomerError; `onProgress` sees every update meanwhile.
+ */
+export function waitForJob(
+  job: Job,
+  onProgress?: (job: Job) => void
+): Promise<Job> {
+  return new Promise((resolve, reject) => {
+    let done = false;
+    let unlisten: (() => void) | undefined;
+
+    const update = (current: Job) => {
+      if (done) return;
+      if (current.state === "running") {
+        onProgress?.(current);
+        return;
+      }
+      done = true;
+      unlisten?.();
+      if (current.state === "succeeded") resolve(current);
+      else reject(current.error);
+    };
+
+    listen<Job>("job-updated", (event) => {
+      if (event.payload.id === job.id) update(event.payload);
+    })
+      .then((fn) => {
+        unlisten = fn;
+        if (done) fn();
+        // The job may have finished before we started listening
+        return api.getJob(job.id);
+      })
+      .then(update)
+      .catch((err) => {
+        done = true;
+        unlisten?.();
+        reject(err);
+      });
+  });
+}
+
+/**
+ * Whether a rejected command returned an IsomerError (optionally of a given kind)
+ */
+export function isIsomerError(
+  err: unknown,
+  kind?: IsomerErrorKind
+): err is IsomerError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    "kind" in err &&
+    "message" in err &&
+    (kind === undefined || (err as IsomerError).kind === kind)
+  );
+}
+
+/**
+ * Human-readable message for anything a command can reject with
+ */
+export function errorMessage(err: unknown): string {
+  if (isIsomerError(err)) return err.message;
+  return err instanceof Error ? err.message : String(err);
+}
+
 export default api;
@@ -4,6 +4,8 @@ export type ServiceStatus =
   | "running"
   | "starting"
   | "stopped"
+  | "error"
+  | "crashloop"
   | { error: string };
 
 export interface ServiceInfo {
@@ -14,6 +16,10 @@ export interface ServiceInfo {
   pid: number | null;
   uptime_secs: number | null;
   version: string | null;
+  restarts: number;
+  error: string | null;
+  /** Runs a locally built binary or alkanes.wasm */
+  custom: boolean;
 }
 
 export interface SystemStatus {
@@ -21,19 +27,61 @@ export interface SystemStatus {
   block_height: number;
   mempool_size: number;
   is_ready: boolean;
+  indexers: IndexerStatus[];
 }
 
+export interface IndexerStatus {
+  service: string;
+  height: number | null;
+  lag: number | null;
+  error: string | null;
+}
+
+export type LogLevel = "trace" | "debug" | "info" | "warn" | "error";
+
 export interface LogEntry {
+  seq: number;
   service: string;
   timestamp: number;
+  level: LogLevel;
+  target: string | null;
   message: string;
   is_stderr: boolean;
 }
 
+export interface LogSearch {
+  pattern?: string | null;
+  services?: string[] | null;
+  min_level?: LogLevel | null;
+  since?: number | null;
+  until?: number | null;
+  limit?: number | null;
+}
+
+export interface LogFilter {
+  services?: string[] | null;
+  min_level?: LogLevel | null;
+}
+
+export interface LogSubscription {
+  id: number;
+  backlog: LogEntry[];
+  cursor: number;
+  gap: boolean;
+}
+
+/** Payload of the `log-entry` event */
+export interface LogBatch {
+  subscription: number;
+  entries: LogEntry[];
+  cursor: number;
+}
+
 export interface Account {
   index: number;
   address: string;
   private_key: string;
+  derivation_path: string;
   balance_sats: number;
 }
 
@@ -41,24 +89,53 @@ export type BinaryStatus =
   | "notinstalled"
   | { downloading: { progress: number } }
   | { installed: { version: string } }
-  | { updateavailable: { current: string; latest: string } };
+  | {
+      updateavailable: {
+        current: string;
+        latest: string;
+        changelog_urls: string[];
+      };
+    };
 
 export interface BinaryInfo {
+  /** Service id, e.g. "bitcoind", or "alkanes-wasm" */
+  id: string;
   service: string;
   status: BinaryStatus;
   path: string;
   size_bytes: number | null;
+  /** The last upgrade can be undone */
+  can_roll_back: boolean;
+  /** Runs a locally built binary or alkanes.wasm from the config */
+  custom: boolean;
+}
+
+export interface BinaryVersion {
+  version: string;
+  installed: boolean;
+  active: boolean;
+  pinned: boolean;
+  available: boolean;
 }
 
 export interface PortConfig {
   bitcoind_rpc: number;
   bitcoind_p2p: number;
   metashrew: number;
-  memshrew: number;
   ord: number;
   esplora_http: number;
   esplora_electrum: number;
   jsonrpc: number;
+  espo_rpc: number;
+  espo_explorer: number;
+}
+
+export interface PortConflict {
+  port: number;
+  service: string;
+  pid: number;
+  process_name: string;
+  is_isomer: boolean;
 }
 
 export interface BitcoindConfig {
@@ -67,15 +144,194 @@ export interface BitcoindConfig {
   fallback_fee: number;
 }
 
+export type MiningMode = "interval" | "on_transaction";
+
 export interface MiningConfig {
   auto_mine: boolean;
+  mode: MiningMode;
   block_interval_ms: number;
   initial_blocks: number;
 }
 
+export interface AutoMineStatus {
+  running: boolean;
+  settings: MiningConfig;
+  address: string | null;
+}
+
+/** Payload of the `block-mined` event */
+export interface MinedBlock {
+  height: number;
+  mode: MiningMode;
+  mempool_size: number;
+}
+
+export type AccountType = "bip84" | "bip86";
+
+export interface AccountsConfig {
+  count: number;
+  account_type: AccountType;
+  balance_btc: number;
+}
+
 export interface IsomerConfig {
   ports: PortConfig;
   bitcoind: BitcoindConfig;
   mining: MiningConfig;
+  accounts: AccountsConfig;
+  restart: RestartConfig;
+  logs: LogConfig;
+  binaries: BinariesConfig;
   mnemonic: string | null;
 }
+
+export interface BinariesConfig {
+  strict_checksums: boolean;
+  /** Pinned version per service id */
+  versions: Partial<Record<string, string>>;
+  /** Look for newer releases when checking binaries */
+  check_updates: boolean;
+  /** Locally built binary per service id */
+  paths: Partial<Record<string, string>>;
+  /** Locally built alkanes.wasm per service id */
+  wasm_paths: Partial<Record<string, string>>;
+}
+
+export interface LogConfig {
+  max_file_size_mb: number;
+  max_files: number;
+}
+
+export type RestartPolicy = "never" | "on_failure" | "always";
+
+export interface RestartConfig {
+  policy: RestartPolicy;
+  max_restarts: number;
+  initial_backoff_ms: number;
+  max_backoff_ms: number;
+  overrides: Partial<Record<string, RestartPolicy>>;
+}
+
+export interface ProfileInfo {
+  name: string;
+  active: boolean;
+  path: string;
+  config: IsomerConfig;
+}
+
+export interface SnapshotManifest {
+  format: number;
+  name: string;
+  created_at: number;
+  block_height: number | null;
+  profile: string;
+  isomer_version: string;
+}
+
+export interface SnapshotInfo {
+  path: string;
+  size_bytes: number;
+  manifest: SnapshotManifest;
+}
+
+export interface ReorgRequest {
+  depth: number;
+  blocks?: number;
+  address?: string;
+  keep_txids?: string[];
+  drop_txids?: string[];
+  timeout_secs?: number;
+}
+
+export interface BlockRef {
+  height: number;
+  hash: string;
+}
+
+export interface ReindexRequest {
+  /** alkanes.wasm to install; the current one is kept if omitted */
+  wasm?: string;
+  /** Delete the whole Metashrew index */
+  wipe?: boolean;
+  /** Keep the index up to this height and rebuild the blocks after it */
+  truncate_to?: number;
+  timeout_secs?: number;
+}
+
+export interface IndexerTip {
+  service: string;
+  height: number | null;
+  hash: string | null;
+  error: string | null;
+}
+
+export interface ReorgReport {
+  old_tip: BlockRef;
+  new_tip: BlockRef;
+  fork_point: BlockRef;
+  disconnected: string[];
+  connected: string[];
+  mined_txids: string[];
+  dropped_txids: string[];
+  indexers: IndexerTip[];
+  converged: boolean;
+  wait_ms: number;
+}
+
+export type IsomerErrorKind =
+  | "binary_not_found"
+  | "port_in_use"
+  | "service_already_running"
+  | "startup_failed"
+  | "job_in_progress"
+  | "rpc"
+  | "download"
+  | "checksum_mismatch"
+  | "unverified"
+  | "config"
+  | "io"
+  | "invalid_input"
+  | "not_found"
+  | "already_exists"
+  | "timeout"
+  | "other";
+
+/** Error rejected by every command; `kind` is stable, `message` is for display */
+export interface IsomerError {
+  kind: IsomerErrorKind;
+  message: string;
+  details: Record<string, unknown>;
+}
+
+export interface Progress {
+  completed: number;
+  total: number;
+  message: string;
+}
+
+export type JobKind =
+  | "start_all"
+  | "stop_all"
+  | "reset"
+  | "start_service"
+  | "stop_service"
+  | "restart_service"
+  | "reindex"
+  | "create_snapshot"
+  | "restore_snapshot"
+  | "clone_profile"
+  | "switch_profile";
+
+export type JobState = "running" | "succeeded" | "failed";
+
+/** Background start/stop/reset operation, updated through `job-updated` events */
+export interface Job {
+  id: number;
+  kind: JobKind;
+  service: string | null;
+  state: JobState;
+  progress: Progress | null;
+  error: IsomerError | null;
+  started_at: number;
+  finished_at: number | null;
+}